@@ -0,0 +1,90 @@
+//! An in-process LRU cache of the full URLs keyed by their short ID, enabled
+//! by the `cache` feature and sized with
+//! [`Config::cache_capacity`](crate::Config::cache_capacity). Without the
+//! feature, the functions in this module do nothing and nothing is cached.
+
+#[cfg(feature = "cache")]
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+#[cfg(feature = "cache")]
+use lru::LruCache;
+
+/// The full URLs cached for a configuration. Every clone of a `Cache` shares
+/// the same entries.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cache {
+    /// The cached full URLs. Unset if caching is disabled.
+    #[cfg(feature = "cache")]
+    entries: Option<Arc<Mutex<LruCache<String, String>>>>,
+}
+
+impl Cache {
+    /// Creates a cache of up to `capacity` full URLs. Nothing is cached if the
+    /// capacity is 0.
+    #[cfg(feature = "cache")]
+    pub(crate) fn new(capacity: usize) -> Self {
+        Cache {
+            entries: NonZeroUsize::new(capacity)
+                .map(|capacity| Arc::new(Mutex::new(LruCache::new(capacity)))),
+        }
+    }
+
+    /// Locks the cached full URLs, if caching is enabled.
+    #[cfg(feature = "cache")]
+    fn lock(&self) -> Option<MutexGuard<'_, LruCache<String, String>>> {
+        self.entries
+            .as_ref()
+            .map(|entries| entries.lock().expect("cache lock was poisoned"))
+    }
+
+    /// Gets the cached full URL of the provided short ID.
+    #[cfg(feature = "cache")]
+    pub(crate) fn get(&self, short_id: &str) -> Option<String> {
+        self.lock()?.get(short_id).cloned()
+    }
+
+    /// Gets the cached full URL of the provided short ID.
+    #[cfg(not(feature = "cache"))]
+    pub(crate) fn get(&self, _short_id: &str) -> Option<String> {
+        None
+    }
+
+    /// Caches the full URL of the provided short ID.
+    #[cfg(feature = "cache")]
+    pub(crate) fn insert(&self, short_id: &str, full_url: &str) {
+        if let Some(mut entries) = self.lock() {
+            entries.put(short_id.to_string(), full_url.to_string());
+        }
+    }
+
+    /// Caches the full URL of the provided short ID.
+    #[cfg(not(feature = "cache"))]
+    pub(crate) fn insert(&self, _short_id: &str, _full_url: &str) {}
+
+    /// Removes the cached full URL of the provided short ID.
+    #[cfg(feature = "cache")]
+    pub(crate) fn remove(&self, short_id: &str) {
+        if let Some(mut entries) = self.lock() {
+            entries.pop(short_id);
+        }
+    }
+
+    /// Removes the cached full URL of the provided short ID.
+    #[cfg(not(feature = "cache"))]
+    pub(crate) fn remove(&self, _short_id: &str) {}
+
+    /// Removes all the cached full URLs.
+    #[cfg(all(feature = "cache", feature = "danger-purge"))]
+    pub(crate) fn clear(&self) {
+        if let Some(mut entries) = self.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Removes all the cached full URLs.
+    #[cfg(all(not(feature = "cache"), feature = "danger-purge"))]
+    pub(crate) fn clear(&self) {}
+}
@@ -1,8 +1,94 @@
+use std::process;
+
 use lazy_static::lazy_static;
 use regex::Regex;
+use shorty::{validate_id, IdError, ShortyError};
 
 const SHORTEN_COMMAND: &str = "shorten";
 const LENGTHEN_COMMAND: &str = "lengthen";
+const EXPORT_COMMAND: &str = "export";
+const HELP_FLAGS: [&str; 2] = ["-h", "--help"];
+const VERSION_FLAGS: [&str; 2] = ["-V", "--version"];
+
+/// The usage information printed for the help flag.
+pub(super) const USAGE: &str = "\
+Usage: shorty <command> <command_arg>
+       shorty export
+       shorty [-h | --help | -V | --version]
+
+Commands:
+    shorten <URL>          Shorten the HTTP or HTTPS URL and print the short ID
+    lengthen <short_id>    Print the full URL stored against the short ID
+    export                 Print all the URLs as line-delimited JSON
+
+Options:
+    -h, --help             Print this help message
+    -V, --version          Print the version
+
+Exit codes:
+    0                      The command succeeded
+    1                      The arguments or the URL are invalid
+    2                      The short ID was not found
+    3                      The database could not be configured or reached,
+                           no unused short ID could be generated, or the
+                           output could not be written";
+
+/// The codes the tool exits with when a command fails. The tool exits with the
+/// code 0 when a command succeeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ExitStatus {
+    /// The arguments of the command, or the URL to shorten, are invalid.
+    Usage = 1,
+    /// The short ID to lengthen was not found.
+    NotFound = 2,
+    /// The database could not be configured or reached, or the output could
+    /// not be written.
+    Backend = 3,
+}
+
+impl ExitStatus {
+    /// Gets the exit status for a command that failed with the provided error.
+    pub fn of(err: &ShortyError) -> Self {
+        match err {
+            ShortyError::InvalidUrl
+            | ShortyError::SelfReference
+            | ShortyError::InvalidId(_)
+            | ShortyError::IdTaken
+            | ShortyError::InvalidMetadataKey(_)
+            | ShortyError::ConflictingOptions(_) => Self::Usage,
+            // Every short ID tried was already stored in the DB.
+            ShortyError::IdGeneration
+            | ShortyError::Database(_)
+            | ShortyError::NonUniqueIndex(_)
+            | ShortyError::NotInitialized
+            | ShortyError::MissingEnv(_) => Self::Backend,
+            #[cfg(feature = "resolve")]
+            ShortyError::Resolve(_) => Self::Backend,
+        }
+    }
+
+    /// Gets the full URL printed by the lengthen command from the outcome of
+    /// fetching the short ID.
+    ///
+    /// # Errors
+    ///
+    /// The status the command exits with and the message it prints if the
+    /// short ID was not found or could not be fetched.
+    pub fn of_lengthened(
+        full_url: Result<Option<String>, ShortyError>,
+    ) -> Result<String, (Self, String)> {
+        match full_url {
+            Ok(Some(full_url)) => Ok(full_url),
+            Ok(None) => Err((Self::NotFound, "not found".to_string())),
+            Err(err) => Err((Self::of(&err), err.to_string())),
+        }
+    }
+
+    /// Exits the process with the current status.
+    pub fn exit(self) -> ! {
+        process::exit(self as i32)
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub(super) enum ShortyCommand {
@@ -12,6 +98,12 @@ pub(super) enum ShortyCommand {
     /// Command to shorten the provided URL. The variant stores the full URL
     /// that the user wants to shorten.
     Shorten(String),
+    /// Command to export all the stored URLs.
+    Export,
+    /// Command to print the usage information.
+    Help,
+    /// Command to print the version of the tool.
+    Version,
 }
 
 pub(super) struct ShortyArgs {
@@ -30,6 +122,19 @@ impl ShortyArgs {
         URL_RE.is_match(test_string)
     }
 
+    /// Checks if the provided short ID could have been created by the library,
+    /// so that malformed short IDs are rejected before reaching the DB.
+    fn validate_short_id(short_id: &str) -> Result<(), &'static str> {
+        match validate_id(short_id) {
+            Ok(()) => Ok(()),
+            Err(IdError::TooShort) => Err("short ID is too short"),
+            Err(IdError::TooLong) => Err("short ID is too long"),
+            Err(IdError::InvalidCharacter(_)) => {
+                Err("short ID must only contain ASCII letters and digits")
+            }
+        }
+    }
+
     pub fn build(
         mut arg_iter: impl Iterator<Item = String>,
     ) -> Result<Self, &'static str> {
@@ -40,6 +145,25 @@ impl ShortyArgs {
             Some(string) => string,
             None => return Err("command positional argument was not found"),
         };
+
+        if HELP_FLAGS.contains(&&command[..]) {
+            return Ok(ShortyArgs {
+                command: ShortyCommand::Help,
+            });
+        }
+        if VERSION_FLAGS.contains(&&command[..]) {
+            return Ok(ShortyArgs {
+                command: ShortyCommand::Version,
+            });
+        }
+
+        let command = command.to_lowercase();
+        if command == EXPORT_COMMAND {
+            return Ok(ShortyArgs {
+                command: ShortyCommand::Export,
+            });
+        }
+
         let command_arg = match arg_iter.next() {
             Some(string) => string,
             None => {
@@ -48,9 +172,12 @@ impl ShortyArgs {
         };
 
         match &command[..] {
-            LENGTHEN_COMMAND => Ok(ShortyArgs {
-                command: ShortyCommand::Lengthen(command_arg),
-            }),
+            LENGTHEN_COMMAND => {
+                Self::validate_short_id(&command_arg)?;
+                Ok(ShortyArgs {
+                    command: ShortyCommand::Lengthen(command_arg),
+                })
+            }
             SHORTEN_COMMAND => {
                 if Self::is_valid_url(&command_arg) {
                     Ok(ShortyArgs {
@@ -71,6 +198,55 @@ mod test {
 
     const SHORTY_EXEC: &str = "shorty";
 
+    #[test]
+    fn should_exit_with_usage_status_for_invalid_input() {
+        for err in [
+            ShortyError::InvalidUrl,
+            ShortyError::IdTaken,
+            ShortyError::InvalidMetadataKey("$key".to_string()),
+            ShortyError::ConflictingOptions("vanity"),
+        ] {
+            assert_eq!(ExitStatus::of(&err), ExitStatus::Usage);
+        }
+    }
+
+    #[test]
+    fn should_exit_with_not_found_status_for_missing_short_id() {
+        assert_eq!(
+            ExitStatus::of_lengthened(Ok(None)),
+            Err((ExitStatus::NotFound, "not found".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_print_lengthened_url() {
+        const URL: &str = "https://example.com";
+
+        assert_eq!(
+            ExitStatus::of_lengthened(Ok(Some(URL.to_string()))),
+            Ok(URL.to_string())
+        );
+    }
+
+    #[test]
+    fn should_exit_with_error_status_when_lengthening_fails() {
+        let outcome =
+            ExitStatus::of_lengthened(Err(ShortyError::NotInitialized));
+
+        assert!(matches!(outcome, Err((ExitStatus::Backend, _))));
+    }
+
+    #[test]
+    fn should_exit_with_backend_status_for_db_errors() {
+        for err in [
+            ShortyError::IdGeneration,
+            ShortyError::NotInitialized,
+            ShortyError::MissingEnv("SHORTY_MONGODB_URI"),
+        ] {
+            assert_eq!(ExitStatus::of(&err), ExitStatus::Backend);
+        }
+    }
+
     #[test]
     fn should_build_shorten_command() {
         let test_url = "https://example.com".to_string();
@@ -105,6 +281,74 @@ mod test {
         );
     }
 
+    #[test]
+    fn should_build_commands_case_insensitively() {
+        let test_url = "https://example.com".to_string();
+
+        for command in ["SHORTEN", "Shorten", "shorten"] {
+            let args = vec![
+                SHORTY_EXEC.to_string(),
+                command.to_string(),
+                test_url.clone(),
+            ];
+            let built_args = ShortyArgs::build(args.into_iter());
+
+            assert!(built_args.is_ok(), "could not build command {command}");
+            assert_eq!(
+                built_args.unwrap().command,
+                ShortyCommand::Shorten(test_url.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn should_not_change_case_of_command_arg() {
+        let test_short_id = "abcdAbc123".to_string();
+        let args = vec![
+            SHORTY_EXEC.to_string(),
+            "LENGTHEN".to_string(),
+            test_short_id.clone(),
+        ];
+        let built_args = ShortyArgs::build(args.into_iter());
+
+        assert!(built_args.is_ok());
+        assert_eq!(
+            built_args.unwrap().command,
+            ShortyCommand::Lengthen(test_short_id)
+        );
+    }
+
+    #[test]
+    fn should_build_export_command() {
+        let args = vec![SHORTY_EXEC.to_string(), EXPORT_COMMAND.to_string()];
+        let built_args = ShortyArgs::build(args.into_iter());
+
+        assert!(built_args.is_ok());
+        assert_eq!(built_args.unwrap().command, ShortyCommand::Export);
+    }
+
+    #[test]
+    fn should_build_help_command() {
+        for flag in HELP_FLAGS {
+            let args = vec![SHORTY_EXEC.to_string(), flag.to_string()];
+            let built_args = ShortyArgs::build(args.into_iter());
+
+            assert!(built_args.is_ok(), "could not build help for {flag}");
+            assert_eq!(built_args.unwrap().command, ShortyCommand::Help);
+        }
+    }
+
+    #[test]
+    fn should_build_version_command() {
+        for flag in VERSION_FLAGS {
+            let args = vec![SHORTY_EXEC.to_string(), flag.to_string()];
+            let built_args = ShortyArgs::build(args.into_iter());
+
+            assert!(built_args.is_ok(), "could not build version for {flag}");
+            assert_eq!(built_args.unwrap().command, ShortyCommand::Version);
+        }
+    }
+
     #[test]
     fn should_require_an_argument_to_shorten() {
         let args = vec![SHORTY_EXEC.to_string(), SHORTEN_COMMAND.to_string()];
@@ -121,6 +365,50 @@ mod test {
         assert!(built_args.is_err());
     }
 
+    #[test]
+    fn should_not_lengthen_short_id_with_illegal_characters() {
+        for test_short_id in ["abc.*123", "abcd?q=1", "abc/../123"] {
+            let args = vec![
+                SHORTY_EXEC.to_string(),
+                LENGTHEN_COMMAND.to_string(),
+                test_short_id.to_string(),
+            ];
+            let built_args = ShortyArgs::build(args.into_iter());
+
+            assert!(built_args.is_err(), "lengthened {test_short_id}");
+        }
+    }
+
+    #[test]
+    fn should_not_lengthen_too_long_short_id() {
+        let args = vec![
+            SHORTY_EXEC.to_string(),
+            LENGTHEN_COMMAND.to_string(),
+            "a".repeat(shorty::MAX_ID_LENGTH + 1),
+        ];
+        let built_args = ShortyArgs::build(args.into_iter());
+
+        assert!(built_args.is_err());
+    }
+
+    #[test]
+    fn should_shorten_internationalized_domain_names() {
+        for test_url in ["https://münchen.de", "https://xn--mnchen-3ya.de"] {
+            let args = vec![
+                SHORTY_EXEC.to_string(),
+                SHORTEN_COMMAND.to_string(),
+                test_url.to_string(),
+            ];
+            let built_args = ShortyArgs::build(args.into_iter());
+
+            assert!(built_args.is_ok(), "could not shorten {test_url}");
+            assert_eq!(
+                built_args.unwrap().command,
+                ShortyCommand::Shorten(test_url.to_string())
+            );
+        }
+    }
+
     #[test]
     fn should_not_shorten_invalid_url() {
         let args = vec![
@@ -0,0 +1,459 @@
+use std::{
+    env,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::sync::OnceCell;
+
+use crate::{
+    cache::Cache,
+    connection::MongoDbConnection,
+    db::{urls::MemoryStore, Backend},
+    id::IdGenerator,
+    view_counts::PendingViews,
+    ShortyError,
+};
+
+/// Name of the environment variable storing the MongoDB connection string.
+const MONGODB_URI_ENV: &str = "SHORTY_MONGODB_URI";
+/// Name of the environment variable storing the name of the database.
+const MONGODB_DATABASE_ENV: &str = "SHORTY_MONGODB_DATABASE";
+
+/// The configuration set with [`init`](crate::init).
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+/// How the length of the generated short IDs grows when they collide, set with
+/// [`Config::grow_id_length`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IdLengthGrowth {
+    /// Number of collisions at a length after which the length grows.
+    pub(crate) collisions: u8,
+    /// Length after which the short IDs stop growing.
+    pub(crate) max_length: usize,
+}
+
+/// When the view counts batched by [`get_url`](crate::get_url) are flushed to
+/// the DB, set with [`Config::batch_view_counts`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ViewCountBatching {
+    /// Number of pending views after which they are flushed.
+    pub(crate) max_pending: usize,
+    /// Time after the oldest pending view after which the pending views are
+    /// flushed.
+    pub(crate) max_delay: Duration,
+}
+
+/// How long resolving the redirects of a URL may wait on each request, set
+/// with [`Config::resolve_timeout`].
+#[cfg(feature = "resolve")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResolveTimeout {
+    /// Time after which connecting to the host of a URL fails.
+    pub(crate) connect: Duration,
+    /// Time after which a request to a URL fails, including connecting to
+    /// its host.
+    pub(crate) request: Duration,
+}
+
+#[cfg(feature = "resolve")]
+impl Default for ResolveTimeout {
+    fn default() -> Self {
+        ResolveTimeout {
+            connect: Duration::from_secs(5),
+            request: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Configuration of the library.
+///
+/// Every clone of a `Config` shares the same cached URLs and pending view
+/// counts. The credentials in the connection string are redacted when a
+/// `Config` is formatted with `{:?}`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::Config;
+/// #
+/// let config = Config::new("mongodb://localhost:27017", "shorty")
+///     .track_views(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Where the URLs are stored.
+    backend: Backend,
+    /// Set if the view count of a URL must be incremented every time it is
+    /// fetched.
+    track_views: bool,
+    /// The URL the short links are served from. URLs pointing at its host
+    /// cannot be shortened.
+    base_url: Option<String>,
+    /// The scheme prepended to the URLs that are a bare host. Such URLs are
+    /// rejected if unset.
+    default_scheme: Option<String>,
+    /// How the length of the generated short IDs grows when they collide.
+    /// The length never grows if unset.
+    id_length_growth: Option<IdLengthGrowth>,
+    /// When the view counts are flushed to the DB if they are batched. Every
+    /// view is written to the DB as it happens if unset.
+    view_count_batching: Option<ViewCountBatching>,
+    /// Generates the short IDs of the URLs.
+    id_generator: IdGenerator,
+    /// The full URLs cached in memory.
+    cache: Cache,
+    /// The views waiting to be flushed if the view counts are batched.
+    pending_views: PendingViews,
+    /// Set once the indexes of the backend were set up lazily.
+    indexes_ensured: Arc<OnceCell<()>>,
+    /// Maximum number of redirects followed to resolve a URL before it is
+    /// stored. URLs are stored as provided if unset.
+    #[cfg(feature = "resolve")]
+    max_redirect_hops: Option<usize>,
+    /// How long resolving a URL may wait on each request.
+    #[cfg(feature = "resolve")]
+    resolve_timeout: ResolveTimeout,
+}
+
+impl Config {
+    /// Creates a new `Config` connecting to the provided database of the
+    /// MongoDB instance at the provided connection string.
+    pub fn new(
+        mongodb_uri: impl Into<String>,
+        database: impl Into<String>,
+    ) -> Self {
+        Config {
+            backend: Backend::MongoDb {
+                uri: mongodb_uri.into(),
+                database: database.into(),
+                client: Arc::default(),
+            },
+            track_views: true,
+            base_url: None,
+            default_scheme: None,
+            id_length_growth: None,
+            view_count_batching: None,
+            id_generator: IdGenerator::default(),
+            cache: Cache::default(),
+            pending_views: PendingViews::default(),
+            indexes_ensured: Arc::default(),
+            #[cfg(feature = "resolve")]
+            max_redirect_hops: None,
+            #[cfg(feature = "resolve")]
+            resolve_timeout: ResolveTimeout::default(),
+        }
+    }
+
+    /// Creates a new `Config` connecting to the provided database of the
+    /// MongoDB instance described by the provided connection, instead of a
+    /// connection string written by hand.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use shorty::{Config, MongoDbConnection};
+    /// #
+    /// let connection = MongoDbConnection::new("localhost")
+    ///     .credentials("shorty", "p@ss:word");
+    /// let config = Config::with_connection(connection, "shorty");
+    /// ```
+    pub fn with_connection(
+        connection: MongoDbConnection,
+        database: impl Into<String>,
+    ) -> Self {
+        let database = database.into();
+        Self::new(connection.to_uri(&database), database)
+    }
+
+    /// Creates a new `Config` storing the URLs in the memory of the current
+    /// process instead of MongoDB. The URLs are lost once the process exits.
+    ///
+    /// Every clone of the returned `Config` shares the same URLs, which makes
+    /// it useful for tests that should not depend on a MongoDB instance.
+    pub fn in_memory() -> Self {
+        Config {
+            backend: Backend::Memory(Arc::new(MemoryStore::default())),
+            track_views: true,
+            base_url: None,
+            default_scheme: None,
+            id_length_growth: None,
+            view_count_batching: None,
+            id_generator: IdGenerator::default(),
+            cache: Cache::default(),
+            pending_views: PendingViews::default(),
+            indexes_ensured: Arc::default(),
+            #[cfg(feature = "resolve")]
+            max_redirect_hops: None,
+            #[cfg(feature = "resolve")]
+            resolve_timeout: ResolveTimeout::default(),
+        }
+    }
+
+    /// Creates a new `Config` storing the URLs in the provided memory store.
+    #[cfg(test)]
+    pub(crate) fn with_memory_store(store: Arc<MemoryStore>) -> Self {
+        Config {
+            backend: Backend::Memory(store),
+            ..Self::in_memory()
+        }
+    }
+
+    /// Creates a new `Config` from the environment variables listed in
+    /// [`sample.env`](https://github.com/waduhek/shorty/blob/main/sample.env).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MissingEnv`](ShortyError::MissingEnv) error if any of the
+    /// environment variables is not set.
+    pub fn from_env() -> Result<Self, ShortyError> {
+        let mongodb_uri = env::var(MONGODB_URI_ENV)
+            .map_err(|_| ShortyError::MissingEnv(MONGODB_URI_ENV))?;
+        let database = env::var(MONGODB_DATABASE_ENV)
+            .map_err(|_| ShortyError::MissingEnv(MONGODB_DATABASE_ENV))?;
+
+        Ok(Self::new(mongodb_uri, database))
+    }
+
+    /// Sets whether [`get_url`](crate::get_url) increments the view count of
+    /// the fetched URL. When disabled, fetching a URL does not write to the
+    /// DB. Defaults to `true`.
+    pub fn track_views(mut self, track_views: bool) -> Self {
+        self.track_views = track_views;
+        self
+    }
+
+    /// Sets the URL the short links are served from, e.g. `https://sho.rt`
+    /// or the bare host `sho.rt`. Creating a URL pointing at the same host
+    /// fails with a [`SelfReference`](ShortyError::SelfReference) error, since
+    /// it would redirect to another short link. Unset by default.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sets the scheme prepended to the URLs that are a bare host before they
+    /// are validated and stored, e.g. `https` to shorten `example.com/sale` as
+    /// `https://example.com/sale`. A bare host is a domain with at least one
+    /// `.` or an IP address, optionally followed by a path. Unset by default,
+    /// so URLs without a scheme are rejected with an
+    /// [`InvalidUrl`](ShortyError::InvalidUrl) error.
+    pub fn default_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.default_scheme = Some(scheme.into());
+        self
+    }
+
+    /// Sets the generated short IDs to grow by one character every time
+    /// `collisions` short IDs of the same length were already taken, until
+    /// they are `max_length` characters long. Creating a URL fails with an
+    /// [`IdGeneration`](ShortyError::IdGeneration) error only once the short
+    /// IDs of `max_length` characters collided `collisions` times as well.
+    ///
+    /// The lengths exclude the prefix of the short IDs. By default the length
+    /// never grows and creating a URL fails after 5 collisions.
+    pub fn grow_id_length(mut self, collisions: u8, max_length: usize) -> Self {
+        self.id_length_growth = Some(IdLengthGrowth {
+            collisions,
+            max_length,
+        });
+        self
+    }
+
+    /// Sets [`get_url`](crate::get_url) to accumulate the view counts in
+    /// memory instead of writing every view to the DB. The pending views are
+    /// written in a single bulk update once `max_pending` views accumulated,
+    /// or by a background task once `max_delay` elapsed since the oldest
+    /// pending view. Views that could not be written are retried by the next
+    /// flush. Unset by default.
+    ///
+    /// The fetched URLs are returned even if flushing their views fails. Views
+    /// of URLs with a maximum number of views are never batched. Call
+    /// [`flush_view_counts`](crate::flush_view_counts) before the process
+    /// exits so that the pending views are not lost.
+    pub fn batch_view_counts(
+        mut self,
+        max_pending: usize,
+        max_delay: Duration,
+    ) -> Self {
+        self.view_count_batching = Some(ViewCountBatching {
+            max_pending,
+            max_delay,
+        });
+        self
+    }
+
+    /// Sets the short IDs to be generated from a random number generator
+    /// seeded with the provided seed, so that the same sequence of short IDs
+    /// is generated every time. Meant for tests that depend on the generated
+    /// short IDs, e.g. to make them collide. By default the short IDs are
+    /// generated from the random number generator of the current thread.
+    ///
+    /// The sequence of short IDs for a seed may change between versions of
+    /// the library.
+    pub fn id_seed(mut self, seed: u64) -> Self {
+        self.id_generator = IdGenerator::seeded(seed);
+        self
+    }
+
+    /// Sets the maximum number of full URLs [`get_url`](crate::get_url) keeps
+    /// in an in-process LRU cache. Defaults to 0, which disables the cache.
+    ///
+    /// Cached URLs are served without fetching them from the DB. Their view
+    /// count is still incremented, but in a background task, so the view
+    /// counts read right after a cached fetch may not include it yet. URLs
+    /// that expire or have a maximum number of views are never cached.
+    #[cfg(feature = "cache")]
+    pub fn cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Cache::new(capacity);
+        self
+    }
+
+    /// Sets the maximum number of HTTP redirects followed to resolve a URL
+    /// before it is shortened. The final destination of the redirects is
+    /// stored instead of the provided URL, so that shortening another short
+    /// link does not create a chain of redirects.
+    ///
+    /// Creating a URL fails with a [`Resolve`](ShortyError::Resolve) error if
+    /// the redirects loop, more than `max_hops` redirects are needed or a
+    /// request times out, see [`resolve_timeout`](Self::resolve_timeout).
+    ///
+    /// Resolving sends requests from the current process to any host the
+    /// shortened URLs and their redirects point at, including loopback and
+    /// private network addresses. Only enable it for trusted users, or behind
+    /// a proxy or firewall that blocks the hosts the process must not reach.
+    #[cfg(feature = "resolve")]
+    pub fn resolve_redirects(mut self, max_hops: usize) -> Self {
+        self.max_redirect_hops = Some(max_hops);
+        self
+    }
+
+    /// Sets how long resolving the redirects of a URL waits on each request
+    /// before failing. Connecting to a host fails after `connect_timeout` and
+    /// a request, including connecting, fails after `request_timeout`.
+    /// Defaults to 5 and 10 seconds.
+    ///
+    /// Each redirect is a new request, so creating a URL may take up to
+    /// `max_hops + 1` times `request_timeout`.
+    #[cfg(feature = "resolve")]
+    pub fn resolve_timeout(
+        mut self,
+        connect_timeout: Duration,
+        request_timeout: Duration,
+    ) -> Self {
+        self.resolve_timeout = ResolveTimeout {
+            connect: connect_timeout,
+            request: request_timeout,
+        };
+        self
+    }
+
+    /// Gets where the URLs are stored.
+    pub(crate) fn get_backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    /// Checks if views of the URLs must be tracked.
+    pub(crate) fn is_tracking_views(&self) -> bool {
+        self.track_views
+    }
+
+    /// Gets the URL the short links are served from, if configured.
+    pub(crate) fn get_base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// Gets the scheme prepended to bare hosts, if they must be coerced.
+    pub(crate) fn get_default_scheme(&self) -> Option<&str> {
+        self.default_scheme.as_deref()
+    }
+
+    /// Gets how the length of the generated short IDs grows, if it grows.
+    pub(crate) fn get_id_length_growth(&self) -> Option<IdLengthGrowth> {
+        self.id_length_growth
+    }
+
+    /// Gets when the batched view counts are flushed, if they are batched.
+    pub(crate) fn get_view_count_batching(&self) -> Option<ViewCountBatching> {
+        self.view_count_batching
+    }
+
+    /// Gets the generator of the short IDs.
+    pub(crate) fn get_id_generator(&self) -> &IdGenerator {
+        &self.id_generator
+    }
+
+    /// Gets the full URLs cached in memory.
+    pub(crate) fn get_cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// Gets the views waiting to be flushed.
+    pub(crate) fn get_pending_views(&self) -> &PendingViews {
+        &self.pending_views
+    }
+
+    /// Gets whether the indexes of the backend were set up lazily.
+    pub(crate) fn get_indexes_ensured(&self) -> &OnceCell<()> {
+        &self.indexes_ensured
+    }
+
+    /// Gets the maximum number of redirects followed to resolve a URL, if
+    /// URLs must be resolved.
+    #[cfg(feature = "resolve")]
+    pub(crate) fn get_max_redirect_hops(&self) -> Option<usize> {
+        self.max_redirect_hops
+    }
+
+    /// Gets how long resolving a URL may wait on each request.
+    #[cfg(feature = "resolve")]
+    pub(crate) fn get_resolve_timeout(&self) -> ResolveTimeout {
+        self.resolve_timeout
+    }
+}
+
+/// Replaces the configuration used by the library.
+pub(crate) fn set(config: Config) {
+    *CONFIG.write().expect("config lock was poisoned") = Some(config);
+}
+
+/// Removes the configuration used by the library.
+///
+/// # Errors
+///
+/// Returns a [`NotInitialized`](ShortyError::NotInitialized) error if no
+/// configuration was set.
+pub(crate) fn take() -> Result<Config, ShortyError> {
+    CONFIG
+        .write()
+        .expect("config lock was poisoned")
+        .take()
+        .ok_or(ShortyError::NotInitialized)
+}
+
+/// Sets the configuration used by the library back after it was taken, unless
+/// another configuration was set in the meantime.
+pub(crate) fn restore(config: Config) {
+    CONFIG
+        .write()
+        .expect("config lock was poisoned")
+        .get_or_insert(config);
+}
+
+/// Checks if a configuration was set.
+pub(crate) fn is_set() -> bool {
+    CONFIG.read().expect("config lock was poisoned").is_some()
+}
+
+/// Gets the configuration used by the library.
+///
+/// # Errors
+///
+/// Returns a [`NotInitialized`](ShortyError::NotInitialized) error if no
+/// configuration was set.
+pub(crate) fn get() -> Result<Config, ShortyError> {
+    CONFIG
+        .read()
+        .expect("config lock was poisoned")
+        .clone()
+        .ok_or(ShortyError::NotInitialized)
+}
@@ -1,32 +1,92 @@
 pub mod urls;
 
-use std::env;
+mod timestamp;
 
-use mongodb::{
-    bson::doc, error::Result as MongoResult, options::ClientOptions, Client,
-    Database,
-};
+use std::{fmt, sync::Arc};
 
-/// Gets a new connection to the DB used by the application.
+use mongodb::{bson::doc, options::ClientOptions, Client, Database};
+use tokio::sync::OnceCell;
+
+use self::urls::MemoryStore;
+use crate::{connection::redact_uri, ShortyError};
+
+/// The outcome of setting up the DB with [`setup_db`](crate::setup_db).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetupReport {
+    /// Names of the indexes created by the setup.
+    pub created: Vec<String>,
+    /// Names of the indexes that already existed before the setup.
+    pub already_present: Vec<String>,
+}
+
+/// Where the data of the library is stored.
+#[derive(Clone)]
+pub(crate) enum Backend {
+    /// The data is stored in a MongoDB database.
+    MongoDb {
+        /// The connection string of the MongoDB instance.
+        uri: String,
+        /// The name of the database.
+        database: String,
+        /// The client connected to the MongoDB instance, created by the first
+        /// operation on the DB.
+        client: Arc<OnceCell<Client>>,
+    },
+    /// The data is stored in the memory of the current process.
+    Memory(Arc<MemoryStore>),
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // The connection string may contain the password.
+            Self::MongoDb {
+                uri,
+                database,
+                client,
+            } => f
+                .debug_struct("MongoDb")
+                .field("uri", &redact_uri(uri))
+                .field("database", database)
+                .field("client", client)
+                .finish(),
+            Self::Memory(store) => {
+                f.debug_tuple("Memory").field(store).finish()
+            }
+        }
+    }
+}
+
+/// Gets a connection to the DB used by the application. The client is created
+/// and the DB is pinged the first time, later calls reuse the same client.
+///
+/// # Errors
+///
+/// Returns a [`Database`](ShortyError::Database) error if the DB could not be
+/// reached.
 ///
 /// # Example
 ///
 /// ```rust,ignore
-/// let db = get_shorty_db_connection().await?;
+/// let db = get_shorty_db_connection(uri, database, &client).await?;
 /// ```
-async fn get_shorty_db_connection() -> MongoResult<Database> {
-    let client_options = ClientOptions::parse(
-        env::var("SHORTY_MONGODB_URI")
-            .expect("could not find connection string"),
-    )
-    .await?;
-    let client = Client::with_options(client_options)?;
-    let database = client.database(
-        &env::var("SHORTY_MONGODB_DATABASE")
-            .expect("could not find database string"),
-    );
-
-    database.run_command(doc! { "ping": 1 }, None).await?;
-
-    Ok(database)
+async fn get_shorty_db_connection(
+    uri: &str,
+    database: &str,
+    client: &OnceCell<Client>,
+) -> Result<Database, ShortyError> {
+    let client = client
+        .get_or_try_init(|| async {
+            let client_options = ClientOptions::parse(uri).await?;
+            let client = Client::with_options(client_options)?;
+
+            client
+                .database(database)
+                .run_command(doc! { "ping": 1 }, None)
+                .await?;
+            Ok::<_, ShortyError>(client)
+        })
+        .await?;
+
+    Ok(client.database(database))
 }
@@ -0,0 +1,34 @@
+//! The timestamp type used by the models.
+//!
+//! The type is picked by the `chrono` (default) or `time` feature. Both types
+//! are stored as a BSON datetime, so documents written with one feature can be
+//! read with the other.
+
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+compile_error!("either the `chrono` or the `time` feature must be enabled");
+
+#[cfg(feature = "chrono")]
+pub(crate) use bson::serde_helpers::chrono_datetime_as_bson_datetime as bson_datetime;
+
+/// The timestamp type stored in the models.
+#[cfg(feature = "chrono")]
+pub(crate) type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Gets the current time.
+#[cfg(feature = "chrono")]
+pub(crate) fn now() -> Timestamp {
+    chrono::Utc::now()
+}
+
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+pub(crate) use bson::serde_helpers::time_0_3_offsetdatetime_as_bson_datetime as bson_datetime;
+
+/// The timestamp type stored in the models.
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+pub(crate) type Timestamp = time::OffsetDateTime;
+
+/// Gets the current time.
+#[cfg(all(feature = "time", not(feature = "chrono")))]
+pub(crate) fn now() -> Timestamp {
+    time::OffsetDateTime::now_utc()
+}
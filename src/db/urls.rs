@@ -1,14 +1,22 @@
+mod memory;
+mod store;
+
+use std::{collections::BTreeMap, time::Duration};
+
 use bson::ser::to_document;
-use chrono::{DateTime, Utc};
-use mongodb::{
-    bson::doc,
-    error::Result as MongoResult,
-    options::{IndexOptions, UpdateModifications},
-    Collection, IndexModel,
-};
+use futures::{Stream, StreamExt};
+use mongodb::{bson::doc, options::UpdateModifications};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+pub(crate) use self::memory::MemoryStore;
+use self::store::UrlStore;
+use super::{
+    timestamp::{self, Timestamp},
+    Backend, SetupReport,
+};
+use crate::{Config, ShortyError};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct UrlModel {
     /// A short ID for the URL.
     short_id: String,
@@ -16,11 +24,35 @@ struct UrlModel {
     full_url: String,
     /// Number of times this link was accessed.
     view_count: u32,
+    /// Number of times this link can be accessed before it stops resolving.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_views: Option<u32>,
+    /// The time after which this link stops resolving. Stored as a BSON
+    /// datetime directly since it is optional.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<bson::DateTime>,
+    /// Set if this link was created by `get_or_create`. Only one such link
+    /// may exist for a full URL.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    canonical: bool,
+    /// Arbitrary labels attached to this link, stored as a subdocument.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+
+    #[serde(with = "timestamp::bson_datetime")]
+    created_at: Timestamp,
+    #[serde(with = "timestamp::bson_datetime")]
+    updated_at: Timestamp,
+}
 
-    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
-    created_at: DateTime<Utc>,
-    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
-    updated_at: DateTime<Utc>,
+/// The view counts that could not be written by
+/// [`Url::increment_view_counts`], along with the reason.
+#[derive(Debug)]
+pub(crate) struct FailedIncrements {
+    /// The error of the first increment that failed.
+    pub(crate) error: ShortyError,
+    /// The views of the short IDs that were not incremented.
+    pub(crate) counts: BTreeMap<String, u32>,
 }
 
 /// The changes that can be performed on the `UrlModel` struct.
@@ -38,7 +70,7 @@ impl From<UrlModelChangeset> for UpdateModifications {
     fn from(value: UrlModelChangeset) -> Self {
         let mut serialised = to_document(&value)
             .expect("could not serialize changeset for update");
-        serialised.insert("updated_at", Utc::now());
+        serialised.insert("updated_at", bson::DateTime::now());
 
         Self::Document(doc! {
             "$set": serialised,
@@ -50,8 +82,8 @@ impl From<UrlModelChangeset> for UpdateModifications {
 pub(crate) struct Url {
     /// The current URL stored.
     model: UrlModel,
-    /// The collection of the model.
-    collection: Collection<UrlModel>,
+    /// The store of the model.
+    store: UrlStore,
     /// Changes to be applied to the model.
     changeset: Option<UrlModelChangeset>,
     /// Set if the current instance was fetched from the DB.
@@ -61,62 +93,99 @@ pub(crate) struct Url {
 impl Url {
     /// Creates a new `Url`.
     pub async fn new(
+        config: &Config,
         short_id: String,
         full_url: &str,
         view_count: u32,
-    ) -> Self {
-        let collection = match Self::get_collection().await {
-            Ok(coll) => coll,
-            Err(err) => {
-                panic!("could not get a collection for URLs: {err}");
-            }
-        };
+    ) -> Result<Self, ShortyError> {
+        let store = Self::get_store(config).await?;
+
+        Ok(Self::new_in(store, short_id, full_url, view_count))
+    }
 
+    /// Creates a new `Url` stored in the provided store.
+    fn new_in(
+        store: UrlStore,
+        short_id: String,
+        full_url: &str,
+        view_count: u32,
+    ) -> Self {
         let model = UrlModel {
             short_id,
             full_url: full_url.to_string(),
             view_count,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            max_views: None,
+            expires_at: None,
+            canonical: false,
+            metadata: BTreeMap::new(),
+            created_at: timestamp::now(),
+            updated_at: timestamp::now(),
         };
 
         Url {
             model,
-            collection,
+            store,
             changeset: None,
             is_fetched_from_db: false,
         }
     }
 
+    /// Sets the number of times a new URL can be accessed before it stops
+    /// resolving.
+    pub fn with_max_views(mut self, max_views: Option<u32>) -> Self {
+        self.model.max_views = max_views;
+        self
+    }
+
+    /// Sets the duration after which a new URL stops resolving.
+    pub fn with_expiry(mut self, expiry: Option<Duration>) -> Self {
+        self.model.expires_at = expiry.map(|expiry| {
+            let now = bson::DateTime::now().timestamp_millis();
+            let expiry = i64::try_from(expiry.as_millis()).unwrap_or(i64::MAX);
+            bson::DateTime::from_millis(now.saturating_add(expiry))
+        });
+        self
+    }
+
+    /// Sets the labels attached to a new URL.
+    pub fn with_metadata(mut self, metadata: BTreeMap<String, String>) -> Self {
+        self.model.metadata = metadata;
+        self
+    }
+
+    /// Marks a new URL as the canonical URL of its full URL. Saving it fails
+    /// with an [`IdTaken`](ShortyError::IdTaken) error if the full URL already
+    /// has a canonical URL.
+    pub fn into_canonical(mut self) -> Self {
+        self.model.canonical = true;
+        self
+    }
+
     /// Constructs a new instance of `Url` from a `UrlModel`.
-    fn from_model(model: UrlModel, collection: Collection<UrlModel>) -> Self {
+    fn from_model(model: UrlModel, store: UrlStore) -> Self {
         Url {
             model,
-            collection,
+            store,
             changeset: None,
             is_fetched_from_db: true,
         }
     }
 
-    async fn _save_from_changeset(&mut self) -> MongoResult<()> {
+    async fn _save_from_changeset(&mut self) -> Result<(), ShortyError> {
         let changeset = self
             .changeset
             .take()
             .expect("trying to save from changeset when changeset is None");
 
-        self.collection
-            .update_one(
-                doc! { "short_id": &self.model.short_id },
-                changeset,
-                None,
-            )
-            .await?;
-        Ok(())
+        self.store.update(&self.model.short_id, changeset).await
     }
 
-    async fn _save_new_url(&self) -> MongoResult<()> {
+    async fn _save_new_url(&mut self) -> Result<(), ShortyError> {
         if !self.is_fetched_from_db {
-            self.collection.insert_one(&self.model, None).await?;
+            self.store.insert(&self.model).await?;
+            // The document now exists in the DB, any further saves must only
+            // update it.
+            self.is_fetched_from_db = true;
         }
         Ok(())
     }
@@ -126,38 +195,173 @@ impl Url {
     /// If any changes were made to the data stored in the model, saves only
     /// those. If a new instance was created, creates a new document in the
     /// database.
-    pub async fn save(&mut self) -> MongoResult<()> {
+    pub async fn save(&mut self) -> Result<(), ShortyError> {
         match self.changeset {
             Some(_) => self._save_from_changeset().await,
             None => self._save_new_url().await,
         }
     }
 
-    /// Increments the view count of the URL. Calling this function multiple
-    /// times without calling [`save`](Url::save) will have no effect on the
-    /// incremented view count.
-    pub fn increment_view_count(&mut self) {
-        self.changeset = Some(UrlModelChangeset {
-            short_id: None,
-            full_url: None,
-            view_count: Some(self.model.view_count + 1),
-        });
+    /// Saves the current URL to the database as a new document. Whenever the
+    /// short ID is already taken, it is replaced with the one returned by
+    /// `next_id` and the save is retried, up to `attempts` times in total. The
+    /// URL is always saved at least once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IdGeneration`](ShortyError::IdGeneration) error if the
+    /// short ID was taken on every attempt. Any other error is returned
+    /// immediately without retrying.
+    pub async fn save_with_retries(
+        &mut self,
+        attempts: usize,
+        mut next_id: impl FnMut() -> String,
+    ) -> Result<(), ShortyError> {
+        for _ in 1..attempts {
+            match self.save().await {
+                Err(ShortyError::IdTaken) => self.update_short_id(next_id()),
+                result => return result,
+            }
+        }
+
+        match self.save().await {
+            Err(ShortyError::IdTaken) => Err(ShortyError::IdGeneration),
+            result => result,
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID in the
+    /// DB without fetching it first.
+    pub async fn increment_view_count_of(
+        config: &Config,
+        short_id: &str,
+    ) -> Result<(), ShortyError> {
+        Self::get_store(config)
+            .await?
+            .increment_view_count(short_id)
+            .await
+    }
+
+    /// Increments the view counts of the URLs with the provided short IDs in
+    /// the DB by the provided number of views, without fetching them first.
+    ///
+    /// # Errors
+    ///
+    /// The views that were not incremented. The other views were incremented
+    /// even if some of them failed.
+    pub async fn increment_view_counts(
+        config: &Config,
+        counts: &BTreeMap<String, u32>,
+    ) -> Result<(), FailedIncrements> {
+        Self::get_store(config)
+            .await
+            .map_err(|error| FailedIncrements {
+                error,
+                counts: counts.clone(),
+            })?
+            .increment_view_counts(counts)
+            .await
+    }
+
+    /// Increments the view count of the URL in the DB unless it already
+    /// reached the provided maximum. The check and the increment are performed
+    /// atomically, so concurrent calls never increment the view count past
+    /// the maximum.
+    ///
+    /// # Returns
+    ///
+    /// Whether the view count was incremented.
+    pub async fn increment_view_count_below(
+        &mut self,
+        max_views: u32,
+    ) -> Result<bool, ShortyError> {
+        let incremented = self
+            .store
+            .increment_view_count_below(&self.model.short_id, max_views)
+            .await?;
+        if incremented {
+            self.model.view_count += 1;
+        }
+
+        Ok(incremented)
     }
 
     /// Fetches a URL with the provided short ID.
-    pub async fn fetch_url(short_id: &str) -> MongoResult<Option<Self>> {
-        let url_collection = Self::get_collection().await?;
+    pub async fn fetch_url(
+        config: &Config,
+        short_id: &str,
+    ) -> Result<Option<Self>, ShortyError> {
+        let url_store = Self::get_store(config).await?;
+
+        let fetched_url = url_store.find_by_short_id(short_id).await?;
+
+        Ok(fetched_url.map(|url_model| Url::from_model(url_model, url_store)))
+    }
+
+    /// Fetches the URL with the provided short ID and increments its view
+    /// count in a single atomic operation, unless it expired or already
+    /// reached its maximum number of views. The fetched URL includes the
+    /// increment.
+    pub async fn fetch_and_increment_view_count(
+        config: &Config,
+        short_id: &str,
+    ) -> Result<Option<Self>, ShortyError> {
+        let url_store = Self::get_store(config).await?;
 
-        let fetched_url = url_collection
-            .find_one(doc! { "short_id": short_id }, None)
+        let fetched_url =
+            url_store.find_and_increment_view_count(short_id).await?;
+
+        Ok(fetched_url.map(|url_model| Url::from_model(url_model, url_store)))
+    }
+
+    /// Fetches a URL that was shortened from the provided full URL and always
+    /// resolves, i.e. that neither expires nor has a maximum number of views.
+    /// If there are multiple such URLs, any one of them is returned.
+    pub async fn fetch_unrestricted_by_full_url(
+        config: &Config,
+        full_url: &str,
+    ) -> Result<Option<Self>, ShortyError> {
+        let url_store = Self::get_store(config).await?;
+
+        let fetched_url = url_store
+            .find_one_unrestricted_by_full_url(full_url)
             .await?;
 
-        match fetched_url {
-            Some(url_model) => {
-                Ok(Some(Url::from_model(url_model, url_collection)))
-            }
-            None => Ok(None),
-        }
+        Ok(fetched_url.map(|url_model| Url::from_model(url_model, url_store)))
+    }
+
+    /// Fetches all the URLs labelled with the provided metadata key and value.
+    pub async fn fetch_by_metadata(
+        config: &Config,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<Self>, ShortyError> {
+        let url_store = Self::get_store(config).await?;
+
+        let url_models = url_store.find_by_metadata(key, value).await?;
+
+        Ok(url_models
+            .into_iter()
+            .map(|url_model| Url::from_model(url_model, url_store.clone()))
+            .collect())
+    }
+
+    /// Fetches the short IDs of all the URLs that were shortened from the
+    /// provided full URL.
+    pub async fn fetch_short_ids_by_full_url(
+        config: &Config,
+        full_url: &str,
+    ) -> Result<Vec<String>, ShortyError> {
+        let url_store = Self::get_store(config).await?;
+
+        let short_ids = url_store
+            .find_by_full_url(full_url)
+            .await?
+            .into_iter()
+            .map(|url_model| url_model.short_id)
+            .collect();
+
+        Ok(short_ids)
     }
 
     /// Gets a reference to the short ID of the current URL.
@@ -170,8 +374,86 @@ impl Url {
         &self.model.full_url
     }
 
+    /// Gets the view count of the current document.
+    pub fn get_view_count(&self) -> u32 {
+        self.model.view_count
+    }
+
+    /// Gets the number of times the current URL can be accessed, if capped.
+    pub fn get_max_views(&self) -> Option<u32> {
+        self.model.max_views
+    }
+
+    /// Gets the labels attached to the current URL.
+    pub fn get_metadata(&self) -> &BTreeMap<String, String> {
+        &self.model.metadata
+    }
+
+    /// Checks if the current URL expires at some point.
+    pub fn has_expiry(&self) -> bool {
+        self.model.expires_at.is_some()
+    }
+
+    /// Checks if the current URL expired.
+    pub fn is_expired(&self) -> bool {
+        self.model
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= bson::DateTime::now())
+    }
+
+    /// Streams all the URLs stored in the DB. The URLs are fetched in batches
+    /// from a cursor as the stream is polled.
+    pub async fn stream_all(
+        config: &Config,
+    ) -> Result<impl Stream<Item = Result<Self, ShortyError>>, ShortyError>
+    {
+        let url_store = Self::get_store(config).await?;
+        let url_models = url_store.stream_all().await?;
+
+        Ok(url_models.map(move |url_model| {
+            let model = url_model?;
+            Ok(Url::from_model(model, url_store.clone()))
+        }))
+    }
+
+    /// Deletes the URL with the provided short ID.
+    ///
+    /// # Returns
+    ///
+    /// Whether a URL was deleted.
+    pub async fn delete(
+        config: &Config,
+        short_id: &str,
+    ) -> Result<bool, ShortyError> {
+        Self::get_store(config).await?.delete(short_id).await
+    }
+
+    /// Counts the URLs stored in the DB.
+    pub async fn count(config: &Config) -> Result<u64, ShortyError> {
+        Self::get_store(config).await?.count().await
+    }
+
+    /// Deletes all the URLs stored in the DB.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted URLs.
+    #[cfg(feature = "danger-purge")]
+    pub async fn delete_all(config: &Config) -> Result<u64, ShortyError> {
+        Self::get_store(config).await?.delete_all().await
+    }
+
     /// Updates the short ID of the current URL.
+    ///
+    /// If the URL has not been saved to the DB yet, the short ID of the model
+    /// is replaced directly so that the next [`save`](Url::save) inserts the
+    /// document with the new ID.
     pub fn update_short_id(&mut self, new_id: String) {
+        if !self.is_fetched_from_db {
+            self.model.short_id = new_id;
+            return;
+        }
+
         match self.changeset.take() {
             Some(change) => {
                 self.changeset = Some(UrlModelChangeset {
@@ -190,23 +472,124 @@ impl Url {
         };
     }
 
-    /// Gets the MongoDB collection for the URLs.
-    async fn get_collection() -> MongoResult<Collection<UrlModel>> {
-        let db = super::get_shorty_db_connection().await?;
-        Ok(db.collection::<UrlModel>("urls"))
+    /// Gets the store for the URLs of the backend of the provided
+    /// configuration.
+    async fn get_store(config: &Config) -> Result<UrlStore, ShortyError> {
+        match config.get_backend() {
+            Backend::MongoDb {
+                uri,
+                database,
+                client,
+            } => {
+                let db = super::get_shorty_db_connection(uri, database, client)
+                    .await?;
+                Ok(UrlStore::MongoDb(db.collection::<UrlModel>("urls")))
+            }
+            Backend::Memory(store) => Ok(UrlStore::Memory(store.clone())),
+        }
     }
 
-    /// Sets up the index required by the `Url` model.
-    pub async fn setup() -> MongoResult<()> {
-        let url_collection = Self::get_collection().await?;
-
-        // Set index on the `short_id` field.
-        let short_id_index = IndexModel::builder()
-            .keys(doc! { "short_id": 1 })
-            .options(IndexOptions::builder().unique(true).build())
-            .build();
+    /// Sets up the indexes required by the `Url` model.
+    pub async fn setup(config: &Config) -> Result<SetupReport, ShortyError> {
+        Self::get_store(config).await?.setup_indexes().await
+    }
 
-        url_collection.create_index(short_id_index, None).await?;
+    /// Sets up the indexes required by the `Url` model if they were not
+    /// already set up by this function for the provided configuration.
+    ///
+    /// Call this function before inserting new documents so that the
+    /// uniqueness of the short IDs is enforced even if [`setup`](Url::setup)
+    /// was never called.
+    pub async fn ensure_indexes(config: &Config) -> Result<(), ShortyError> {
+        config
+            .get_indexes_ensured()
+            .get_or_try_init(|| async { Self::setup(config).await.map(|_| ()) })
+            .await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, io, sync::Arc};
+
+    use bson::{de::from_document, ser::to_document};
+
+    use super::{MemoryStore, Url, UrlModel, UrlStore};
+    use crate::ShortyError;
+
+    /// A short ID that was already saved must be replaced and the URL saved
+    /// with the new short ID.
+    #[tokio::test]
+    async fn taken_short_id_retried() {
+        let store = UrlStore::Memory(Arc::new(MemoryStore::default()));
+        let new_url = |short_id: &str| {
+            Url::new_in(
+                store.clone(),
+                short_id.to_string(),
+                "https://example.com",
+                0,
+            )
+        };
+        new_url("taken").save().await.unwrap();
+
+        let mut url_obj = new_url("taken");
+        let result = url_obj.save_with_retries(5, || "free".to_string()).await;
+
+        assert!(result.is_ok());
+        assert_eq!(url_obj.get_short_id(), "free");
+    }
+
+    /// Errors other than a taken short ID, e.g. network errors, must be
+    /// returned without regenerating the short ID.
+    #[tokio::test]
+    async fn other_errors_not_retried() {
+        let memory_store = Arc::new(MemoryStore::failing_inserts(|| {
+            mongodb::error::Error::from(io::ErrorKind::ConnectionReset).into()
+        }));
+        let store = UrlStore::Memory(memory_store.clone());
+
+        let mut url_obj = Url::new_in(
+            store,
+            "abcd1234".to_string(),
+            "https://example.com",
+            0,
+        );
+        let result = url_obj
+            .save_with_retries(5, || panic!("short ID was regenerated"))
+            .await;
+
+        assert!(matches!(result, Err(ShortyError::Database(_))));
+        assert_eq!(memory_store.operation_count("insert"), 1);
+    }
+
+    /// The timestamps must be stored as a BSON datetime irrespective of the
+    /// enabled timestamp feature and must survive a round trip.
+    #[test]
+    fn timestamps_round_trip_as_bson_datetime() {
+        const TIMESTAMP_MILLIS: i64 = 1_690_000_000_123;
+
+        let timestamp = bson::DateTime::from_millis(TIMESTAMP_MILLIS);
+        let model = UrlModel {
+            short_id: "abcdAbc123".to_string(),
+            full_url: "https://example.com".to_string(),
+            view_count: 0,
+            max_views: None,
+            expires_at: None,
+            canonical: false,
+            metadata: BTreeMap::new(),
+            created_at: timestamp.into(),
+            updated_at: timestamp.into(),
+        };
+
+        let document =
+            to_document(&model).expect("could not serialise the model");
+        assert_eq!(document.get_datetime("created_at"), Ok(&timestamp));
+        assert_eq!(document.get_datetime("updated_at"), Ok(&timestamp));
+
+        let deserialised: UrlModel =
+            from_document(document).expect("could not deserialise the model");
+        assert_eq!(deserialised.created_at, model.created_at);
+        assert_eq!(deserialised.updated_at, model.updated_at);
+    }
+}
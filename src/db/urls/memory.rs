@@ -0,0 +1,462 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Mutex,
+};
+
+use super::{FailedIncrements, UrlModel, UrlModelChangeset};
+use crate::{
+    db::{timestamp, SetupReport},
+    ShortyError,
+};
+
+/// Stores the URLs in the memory of the current process.
+///
+/// The store behaves like the MongoDB collection with a unique index on the
+/// short IDs, so it can be used in place of MongoDB in tests.
+#[derive(Debug, Default)]
+pub(crate) struct MemoryStore {
+    /// The stored URLs keyed by their short ID.
+    urls: Mutex<HashMap<String, UrlModel>>,
+    /// Names of the indexes set up on the store. The store always behaves as
+    /// if its indexes exist, the names are only kept to report the setup.
+    indexes: Mutex<HashSet<String>>,
+    /// Creates the error returned by every insert, to simulate DB failures.
+    #[cfg(test)]
+    insert_error: Option<fn() -> ShortyError>,
+    /// The short ID whose view count increments fail, to simulate DB
+    /// failures.
+    #[cfg(test)]
+    failing_increments_of: Option<&'static str>,
+    /// Creates the error returned by the failing view count increments.
+    #[cfg(test)]
+    increment_error: Option<fn() -> ShortyError>,
+    /// Number of times each operation was performed on the store.
+    #[cfg(test)]
+    operation_counts: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl MemoryStore {
+    /// Creates a store failing every insert with the error created by
+    /// `insert_error`.
+    #[cfg(test)]
+    pub(super) fn failing_inserts(insert_error: fn() -> ShortyError) -> Self {
+        MemoryStore {
+            insert_error: Some(insert_error),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a store failing the view count increments of `short_id` in
+    /// [`increment_view_counts`](MemoryStore::increment_view_counts) with the
+    /// error created by `increment_error`.
+    #[cfg(test)]
+    pub(crate) fn failing_increments(
+        short_id: &'static str,
+        increment_error: fn() -> ShortyError,
+    ) -> Self {
+        MemoryStore {
+            failing_increments_of: Some(short_id),
+            increment_error: Some(increment_error),
+            ..Default::default()
+        }
+    }
+
+    /// Checks if the view count increments of the provided short ID must
+    /// fail, returning the error they fail with.
+    #[cfg(test)]
+    fn increment_error_of(&self, short_id: &str) -> Option<ShortyError> {
+        self.increment_error
+            .filter(|_| self.failing_increments_of == Some(short_id))
+            .map(|increment_error| increment_error())
+    }
+
+    /// Checks if the view count increments of the provided short ID must
+    /// fail, returning the error they fail with.
+    #[cfg(not(test))]
+    fn increment_error_of(&self, _short_id: &str) -> Option<ShortyError> {
+        None
+    }
+
+    /// Gets the number of times the operation with the provided name, e.g.
+    /// `find_by_short_id`, was performed.
+    #[cfg(test)]
+    pub(crate) fn operation_count(&self, name: &str) -> usize {
+        let operation_counts = self
+            .operation_counts
+            .lock()
+            .expect("memory store lock was poisoned");
+        operation_counts.get(name).copied().unwrap_or_default()
+    }
+
+    /// Records that the operation with the provided name was performed.
+    #[cfg(test)]
+    fn record_operation(&self, name: &'static str) {
+        *self
+            .operation_counts
+            .lock()
+            .expect("memory store lock was poisoned")
+            .entry(name)
+            .or_default() += 1;
+    }
+
+    /// Records that the operation with the provided name was performed.
+    #[cfg(not(test))]
+    fn record_operation(&self, _name: &'static str) {}
+
+    /// Locks the stored URLs.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, UrlModel>> {
+        self.urls.lock().expect("memory store lock was poisoned")
+    }
+
+    /// Inserts a new URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IdTaken`](ShortyError::IdTaken) error if a URL with the
+    /// same short ID is already stored or if the URL is canonical and a
+    /// canonical URL with the same full URL is already stored.
+    pub(super) fn insert(&self, model: &UrlModel) -> Result<(), ShortyError> {
+        self.record_operation("insert");
+        #[cfg(test)]
+        if let Some(insert_error) = self.insert_error {
+            return Err(insert_error());
+        }
+
+        let mut urls = self.lock();
+        if urls.contains_key(&model.short_id) {
+            return Err(ShortyError::IdTaken);
+        }
+        if model.canonical
+            && urls.values().any(|stored| {
+                stored.canonical && stored.full_url == model.full_url
+            })
+        {
+            return Err(ShortyError::IdTaken);
+        }
+
+        urls.insert(model.short_id.clone(), model.clone());
+        Ok(())
+    }
+
+    /// Applies the changeset to the URL with the provided short ID. Does
+    /// nothing if the short ID is not stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IdTaken`](ShortyError::IdTaken) error if the changeset
+    /// changes the short ID to one that is already stored.
+    pub(super) fn update(
+        &self,
+        short_id: &str,
+        changeset: UrlModelChangeset,
+    ) -> Result<(), ShortyError> {
+        self.record_operation("update");
+        let mut urls = self.lock();
+
+        if let Some(new_id) = &changeset.short_id {
+            if new_id != short_id && urls.contains_key(new_id) {
+                return Err(ShortyError::IdTaken);
+            }
+        }
+
+        let mut model = match urls.remove(short_id) {
+            Some(model) => model,
+            None => return Ok(()),
+        };
+
+        if let Some(new_id) = changeset.short_id {
+            model.short_id = new_id;
+        }
+        if let Some(full_url) = changeset.full_url {
+            model.full_url = full_url;
+        }
+        if let Some(view_count) = changeset.view_count {
+            model.view_count = view_count;
+        }
+        model.updated_at = timestamp::now();
+
+        urls.insert(model.short_id.clone(), model);
+        Ok(())
+    }
+
+    /// Increments the view count of the URL with the provided short ID. Does
+    /// nothing if the short ID is not stored.
+    pub(super) fn increment_view_count(&self, short_id: &str) {
+        self.record_operation("increment_view_count");
+        if let Some(model) = self.lock().get_mut(short_id) {
+            model.view_count += 1;
+            model.updated_at = timestamp::now();
+        }
+    }
+
+    /// Increments the view counts of the URLs with the provided short IDs by
+    /// the provided number of views at once. Short IDs that are not stored
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// The views that were not incremented. The other views are incremented
+    /// even if some of them failed, like an unordered bulk update.
+    pub(super) fn increment_view_counts(
+        &self,
+        counts: &BTreeMap<String, u32>,
+    ) -> Result<(), FailedIncrements> {
+        self.record_operation("increment_view_counts");
+        let mut urls = self.lock();
+        let mut first_error = None;
+        let mut failed = BTreeMap::new();
+        for (short_id, views) in counts {
+            if let Some(error) = self.increment_error_of(short_id) {
+                first_error.get_or_insert(error);
+                failed.insert(short_id.clone(), *views);
+                continue;
+            }
+            if let Some(model) = urls.get_mut(short_id) {
+                model.view_count = model.view_count.saturating_add(*views);
+                model.updated_at = timestamp::now();
+            }
+        }
+
+        match first_error {
+            Some(error) => Err(FailedIncrements {
+                error,
+                counts: failed,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID if it
+    /// is below the provided maximum.
+    ///
+    /// # Returns
+    ///
+    /// Whether the view count was incremented.
+    pub(super) fn increment_view_count_below(
+        &self,
+        short_id: &str,
+        max_views: u32,
+    ) -> bool {
+        self.record_operation("increment_view_count_below");
+        match self.lock().get_mut(short_id) {
+            Some(model) if model.view_count < max_views => {
+                model.view_count += 1;
+                model.updated_at = timestamp::now();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID unless
+    /// it expired or already reached its maximum number of views.
+    ///
+    /// # Returns
+    ///
+    /// The updated URL, if it was incremented.
+    pub(super) fn find_and_increment_view_count(
+        &self,
+        short_id: &str,
+    ) -> Option<UrlModel> {
+        self.record_operation("find_and_increment_view_count");
+        let mut urls = self.lock();
+        let model = urls.get_mut(short_id)?;
+
+        let is_expired = model
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= bson::DateTime::now());
+        let is_exhausted = model
+            .max_views
+            .is_some_and(|max_views| model.view_count >= max_views);
+        if is_expired || is_exhausted {
+            return None;
+        }
+
+        model.view_count += 1;
+        model.updated_at = timestamp::now();
+        Some(model.clone())
+    }
+
+    /// Sets up the indexes with the provided names.
+    ///
+    /// # Returns
+    ///
+    /// The indexes that were set up and the ones that already existed.
+    pub(super) fn setup_indexes(&self, names: &[String]) -> SetupReport {
+        let mut indexes =
+            self.indexes.lock().expect("memory store lock was poisoned");
+
+        let mut report = SetupReport::default();
+        for name in names {
+            if indexes.insert(name.clone()) {
+                report.created.push(name.clone());
+            } else {
+                report.already_present.push(name.clone());
+            }
+        }
+
+        report
+    }
+
+    /// Finds the URL with the provided short ID.
+    pub(super) fn find_by_short_id(&self, short_id: &str) -> Option<UrlModel> {
+        self.record_operation("find_by_short_id");
+        self.lock().get(short_id).cloned()
+    }
+
+    /// Finds all the URLs shortened from the provided full URL.
+    pub(super) fn find_by_full_url(&self, full_url: &str) -> Vec<UrlModel> {
+        self.record_operation("find_by_full_url");
+        self.lock()
+            .values()
+            .filter(|model| model.full_url == full_url)
+            .cloned()
+            .collect()
+    }
+
+    /// Finds any one of the URLs shortened from the provided full URL that
+    /// neither expire nor have a maximum number of views.
+    pub(super) fn find_one_unrestricted_by_full_url(
+        &self,
+        full_url: &str,
+    ) -> Option<UrlModel> {
+        self.record_operation("find_one_unrestricted_by_full_url");
+        self.lock()
+            .values()
+            .find(|model| {
+                model.full_url == full_url
+                    && model.max_views.is_none()
+                    && model.expires_at.is_none()
+            })
+            .cloned()
+    }
+
+    /// Finds all the URLs labelled with the provided metadata key and value.
+    pub(super) fn find_by_metadata(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> Vec<UrlModel> {
+        self.record_operation("find_by_metadata");
+        self.lock()
+            .values()
+            .filter(|model| {
+                model
+                    .metadata
+                    .get(key)
+                    .is_some_and(|stored| stored == value)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Gets a snapshot of all the stored URLs.
+    pub(super) fn all(&self) -> Vec<UrlModel> {
+        self.record_operation("all");
+        self.lock().values().cloned().collect()
+    }
+
+    /// Deletes the URL with the provided short ID.
+    ///
+    /// # Returns
+    ///
+    /// Whether a URL was deleted.
+    pub(super) fn delete(&self, short_id: &str) -> bool {
+        self.record_operation("delete");
+        self.lock().remove(short_id).is_some()
+    }
+
+    /// Counts the stored URLs.
+    pub(super) fn count(&self) -> u64 {
+        self.record_operation("count");
+        self.lock().len() as u64
+    }
+
+    /// Deletes all the stored URLs.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted URLs.
+    #[cfg(feature = "danger-purge")]
+    pub(super) fn delete_all(&self) -> u64 {
+        self.record_operation("delete_all");
+        let mut urls = self.lock();
+        let num_deleted = urls.len() as u64;
+        urls.clear();
+        num_deleted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_model(short_id: &str) -> UrlModel {
+        UrlModel {
+            short_id: short_id.to_string(),
+            full_url: "https://example.com".to_string(),
+            view_count: 0,
+            max_views: None,
+            expires_at: None,
+            canonical: false,
+            metadata: BTreeMap::new(),
+            created_at: timestamp::now(),
+            updated_at: timestamp::now(),
+        }
+    }
+
+    #[test]
+    fn duplicate_short_id_rejected() {
+        let store = MemoryStore::default();
+
+        assert!(store.insert(&new_model("abcd1234")).is_ok());
+        assert!(matches!(
+            store.insert(&new_model("abcd1234")),
+            Err(ShortyError::IdTaken)
+        ));
+    }
+
+    #[test]
+    fn changeset_applied() {
+        let store = MemoryStore::default();
+        store.insert(&new_model("abcd1234")).unwrap();
+
+        let changeset = UrlModelChangeset {
+            short_id: Some("efgh5678".to_string()),
+            full_url: None,
+            view_count: Some(1),
+        };
+        store.update("abcd1234", changeset).unwrap();
+
+        assert!(store.find_by_short_id("abcd1234").is_none());
+        let model = store.find_by_short_id("efgh5678").unwrap();
+        assert_eq!(model.view_count, 1);
+        assert_eq!(model.full_url, "https://example.com");
+    }
+
+    #[test]
+    fn view_count_not_incremented_past_maximum() {
+        let store = MemoryStore::default();
+        store.insert(&new_model("abcd1234")).unwrap();
+
+        assert!(store.increment_view_count_below("abcd1234", 1));
+        assert!(!store.increment_view_count_below("abcd1234", 1));
+        assert_eq!(store.find_by_short_id("abcd1234").unwrap().view_count, 1);
+    }
+
+    #[test]
+    fn urls_found_by_metadata() {
+        let store = MemoryStore::default();
+        let mut model = new_model("abcd1234");
+        model
+            .metadata
+            .insert("team".to_string(), "growth".to_string());
+        store.insert(&model).unwrap();
+        store.insert(&new_model("efgh5678")).unwrap();
+
+        let found = store.find_by_metadata("team", "growth");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].short_id, "abcd1234");
+        assert!(store.find_by_metadata("team", "search").is_empty());
+    }
+}
@@ -0,0 +1,678 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{doc, Document},
+    error::{Error, ErrorKind, WriteError, WriteFailure},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use super::{FailedIncrements, MemoryStore, UrlModel, UrlModelChangeset};
+use crate::{db::SetupReport, ShortyError};
+
+/// The code of the MongoDB error returned when a unique index is violated.
+const DUPLICATE_KEY_CODE: i32 = 11000;
+/// The code of the MongoDB error returned when a collection does not exist.
+const NAMESPACE_NOT_FOUND_CODE: i32 = 26;
+
+/// Converts the error returned by a MongoDB write to a [`ShortyError`]. A
+/// violation of the unique index on the short IDs is converted to an
+/// [`IdTaken`](ShortyError::IdTaken) error, like the memory store does.
+fn from_write_error(err: Error) -> ShortyError {
+    match *err.kind {
+        ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+            if write_error.code == DUPLICATE_KEY_CODE =>
+        {
+            ShortyError::IdTaken
+        }
+        _ => err.into(),
+    }
+}
+
+/// The storage of the URLs.
+#[derive(Debug, Clone)]
+pub(super) enum UrlStore {
+    /// The URLs are stored in a MongoDB collection.
+    MongoDb(Collection<UrlModel>),
+    /// The URLs are stored in the memory of the current process.
+    Memory(Arc<MemoryStore>),
+}
+
+impl UrlStore {
+    /// Inserts a new URL.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.insert", skip_all)
+    )]
+    pub async fn insert(&self, model: &UrlModel) -> Result<(), ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                collection
+                    .insert_one(model, None)
+                    .await
+                    .map_err(from_write_error)?;
+                Ok(())
+            }
+            Self::Memory(store) => store.insert(model),
+        }
+    }
+
+    /// Applies the changeset to the URL with the provided short ID.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.update", skip_all)
+    )]
+    pub async fn update(
+        &self,
+        short_id: &str,
+        changeset: UrlModelChangeset,
+    ) -> Result<(), ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                collection
+                    .update_one(doc! { "short_id": short_id }, changeset, None)
+                    .await
+                    .map_err(from_write_error)?;
+                Ok(())
+            }
+            Self::Memory(store) => store.update(short_id, changeset),
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.increment_view_count", skip_all)
+    )]
+    pub async fn increment_view_count(
+        &self,
+        short_id: &str,
+    ) -> Result<(), ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                collection
+                    .update_one(
+                        doc! { "short_id": short_id },
+                        doc! {
+                            "$inc": { "view_count": 1 },
+                            "$set": { "updated_at": bson::DateTime::now() },
+                        },
+                        None,
+                    )
+                    .await?;
+                Ok(())
+            }
+            Self::Memory(store) => {
+                store.increment_view_count(short_id);
+                Ok(())
+            }
+        }
+    }
+
+    /// Increments the view counts of the URLs with the provided short IDs by
+    /// the provided number of views. All the increments are sent to the DB in
+    /// a single unordered bulk update, so an increment that fails does not
+    /// prevent the others.
+    ///
+    /// # Errors
+    ///
+    /// The views that were not incremented, which are all of them if the bulk
+    /// update could not be sent.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.increment_view_counts", skip_all)
+    )]
+    pub async fn increment_view_counts(
+        &self,
+        counts: &BTreeMap<String, u32>,
+    ) -> Result<(), FailedIncrements> {
+        let all_failed = |error: ShortyError| FailedIncrements {
+            error,
+            counts: counts.clone(),
+        };
+
+        match self {
+            Self::MongoDb(collection) => {
+                let updated_at = bson::DateTime::now();
+                let updates: Vec<Document> = counts
+                    .iter()
+                    .map(|(short_id, views)| {
+                        doc! {
+                            "q": { "short_id": short_id },
+                            "u": {
+                                "$inc": { "view_count": i64::from(*views) },
+                                "$set": { "updated_at": updated_at },
+                            },
+                        }
+                    })
+                    .collect();
+
+                let namespace = collection.namespace();
+                let response = collection
+                    .client()
+                    .database(&namespace.db)
+                    .run_command(
+                        doc! {
+                            "update": namespace.coll,
+                            "updates": updates,
+                            "ordered": false,
+                        },
+                        None,
+                    )
+                    .await
+                    .map_err(|err| all_failed(err.into()))?;
+
+                // The update command reports the failed updates in its
+                // response instead of failing. The index of a failed update is
+                // its position in the updates, i.e. in the counts.
+                let Ok(write_errors) = response.get_array("writeErrors") else {
+                    return Ok(());
+                };
+                let Some(first_error) = write_errors.first().cloned() else {
+                    return Ok(());
+                };
+                let first_error: WriteError = bson::from_bson(first_error)
+                    .map_err(|err| all_failed(Error::from(err).into()))?;
+
+                Err(FailedIncrements {
+                    error: Error::from(ErrorKind::Write(
+                        WriteFailure::WriteError(first_error),
+                    ))
+                    .into(),
+                    counts: write_errors
+                        .iter()
+                        .filter_map(|write_error| {
+                            let index = write_error
+                                .as_document()?
+                                .get_i32("index")
+                                .ok()?;
+                            counts.iter().nth(usize::try_from(index).ok()?)
+                        })
+                        .map(|(short_id, views)| (short_id.clone(), *views))
+                        .collect(),
+                })
+            }
+            Self::Memory(store) => store.increment_view_counts(counts),
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID if it
+    /// is below the provided maximum. The check and the increment are
+    /// performed in a single atomic update.
+    ///
+    /// # Returns
+    ///
+    /// Whether the view count was incremented.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.increment_view_count_below", skip_all)
+    )]
+    pub async fn increment_view_count_below(
+        &self,
+        short_id: &str,
+        max_views: u32,
+    ) -> Result<bool, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                let result = collection
+                    .update_one(
+                        doc! {
+                            "short_id": short_id,
+                            "view_count": { "$lt": max_views },
+                        },
+                        doc! {
+                            "$inc": { "view_count": 1 },
+                            "$set": { "updated_at": bson::DateTime::now() },
+                        },
+                        None,
+                    )
+                    .await?;
+                Ok(result.modified_count > 0)
+            }
+            Self::Memory(store) => {
+                Ok(store.increment_view_count_below(short_id, max_views))
+            }
+        }
+    }
+
+    /// Increments the view count of the URL with the provided short ID unless
+    /// it expired or already reached its maximum number of views. The check,
+    /// the increment and the read of the updated URL are performed in a single
+    /// atomic operation.
+    ///
+    /// # Returns
+    ///
+    /// The updated URL, if it was incremented.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "db.find_and_increment_view_count",
+            skip_all
+        )
+    )]
+    pub async fn find_and_increment_view_count(
+        &self,
+        short_id: &str,
+    ) -> Result<Option<UrlModel>, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                let now = bson::DateTime::now();
+                let options = FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build();
+                Ok(collection
+                    .find_one_and_update(
+                        doc! {
+                            "short_id": short_id,
+                            "$and": [
+                                { "$or": [
+                                    { "expires_at": { "$exists": false } },
+                                    { "expires_at": { "$gt": now } },
+                                ] },
+                                { "$or": [
+                                    { "max_views": { "$exists": false } },
+                                    { "$expr": {
+                                        "$lt": ["$view_count", "$max_views"],
+                                    } },
+                                ] },
+                            ],
+                        },
+                        doc! {
+                            "$inc": { "view_count": 1 },
+                            "$set": { "updated_at": now },
+                        },
+                        options,
+                    )
+                    .await?)
+            }
+            Self::Memory(store) => {
+                Ok(store.find_and_increment_view_count(short_id))
+            }
+        }
+    }
+
+    /// Finds the URL with the provided short ID.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.find_by_short_id", skip_all)
+    )]
+    pub async fn find_by_short_id(
+        &self,
+        short_id: &str,
+    ) -> Result<Option<UrlModel>, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => Ok(collection
+                .find_one(doc! { "short_id": short_id }, None)
+                .await?),
+            Self::Memory(store) => Ok(store.find_by_short_id(short_id)),
+        }
+    }
+
+    /// Finds any one of the URLs shortened from the provided full URL that
+    /// neither expire nor have a maximum number of views, i.e. that always
+    /// resolve.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "db.find_one_unrestricted_by_full_url",
+            skip_all
+        )
+    )]
+    pub async fn find_one_unrestricted_by_full_url(
+        &self,
+        full_url: &str,
+    ) -> Result<Option<UrlModel>, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => Ok(collection
+                .find_one(
+                    doc! {
+                        "full_url": full_url,
+                        "max_views": { "$exists": false },
+                        "expires_at": { "$exists": false },
+                    },
+                    None,
+                )
+                .await?),
+            Self::Memory(store) => {
+                Ok(store.find_one_unrestricted_by_full_url(full_url))
+            }
+        }
+    }
+
+    /// Finds all the URLs shortened from the provided full URL.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.find_by_full_url", skip_all)
+    )]
+    pub async fn find_by_full_url(
+        &self,
+        full_url: &str,
+    ) -> Result<Vec<UrlModel>, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => Ok(collection
+                .find(doc! { "full_url": full_url }, None)
+                .await?
+                .try_collect()
+                .await?),
+            Self::Memory(store) => Ok(store.find_by_full_url(full_url)),
+        }
+    }
+
+    /// Finds all the URLs labelled with the provided metadata key and value.
+    /// The key must not contain `.` or start with `$`, since it is used as a
+    /// field path.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.find_by_metadata", skip_all)
+    )]
+    pub async fn find_by_metadata(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<UrlModel>, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => Ok(collection
+                .find(doc! { format!("metadata.{key}"): value }, None)
+                .await?
+                .try_collect()
+                .await?),
+            Self::Memory(store) => Ok(store.find_by_metadata(key, value)),
+        }
+    }
+
+    /// Streams all the stored URLs.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.stream_all", skip_all)
+    )]
+    pub async fn stream_all(
+        &self,
+    ) -> Result<BoxStream<'static, Result<UrlModel, ShortyError>>, ShortyError>
+    {
+        match self {
+            Self::MongoDb(collection) => {
+                let cursor = collection.find(None, None).await?;
+                Ok(cursor.map_err(ShortyError::from).boxed())
+            }
+            Self::Memory(store) => {
+                Ok(futures::stream::iter(store.all().into_iter().map(Ok))
+                    .boxed())
+            }
+        }
+    }
+
+    /// Deletes the URL with the provided short ID.
+    ///
+    /// # Returns
+    ///
+    /// Whether a URL was deleted.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.delete", skip_all)
+    )]
+    pub async fn delete(&self, short_id: &str) -> Result<bool, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                let result = collection
+                    .delete_one(doc! { "short_id": short_id }, None)
+                    .await?;
+                Ok(result.deleted_count > 0)
+            }
+            Self::Memory(store) => Ok(store.delete(short_id)),
+        }
+    }
+
+    /// Counts the stored URLs.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.count", skip_all)
+    )]
+    pub async fn count(&self) -> Result<u64, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                Ok(collection.count_documents(None, None).await?)
+            }
+            Self::Memory(store) => Ok(store.count()),
+        }
+    }
+
+    /// Deletes all the stored URLs.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted URLs.
+    #[cfg(feature = "danger-purge")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.delete_all", skip_all)
+    )]
+    pub async fn delete_all(&self) -> Result<u64, ShortyError> {
+        match self {
+            Self::MongoDb(collection) => {
+                let result = collection.delete_many(doc! {}, None).await?;
+                Ok(result.deleted_count)
+            }
+            Self::Memory(store) => Ok(store.delete_all()),
+        }
+    }
+
+    /// Sets up the indexes of the store. Indexes with the same keys as a
+    /// required index are assumed to be that index and are not created again.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NonUniqueIndex`](ShortyError::NonUniqueIndex) error if an
+    /// existing index has the keys of a required unique index but is not
+    /// unique or has a different partial filter.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "db.setup_indexes", skip_all)
+    )]
+    pub async fn setup_indexes(&self) -> Result<SetupReport, ShortyError> {
+        let indexes = required_indexes();
+
+        let collection = match self {
+            Self::MongoDb(collection) => collection,
+            // The memory store always enforces unique short IDs and does not
+            // need indexes.
+            Self::Memory(store) => {
+                let names: Vec<_> = indexes.iter().map(index_name).collect();
+                return Ok(store.setup_indexes(&names));
+            }
+        };
+
+        let existing_indexes = list_indexes(collection).await?;
+        if let Some(index) = existing_indexes.iter().find(|existing_index| {
+            indexes
+                .iter()
+                .any(|index| !enforces_uniqueness_of(existing_index, index))
+        }) {
+            return Err(ShortyError::NonUniqueIndex(index_name(index)));
+        }
+
+        let mut report = SetupReport::default();
+        for index in indexes {
+            let is_present = existing_indexes
+                .iter()
+                .any(|existing_index| existing_index.keys == index.keys);
+            let name = index_name(&index);
+
+            if is_present {
+                report.already_present.push(name);
+            } else {
+                collection.create_index(index, None).await?;
+                report.created.push(name);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Gets the indexes required by the URLs.
+fn required_indexes() -> [IndexModel; 4] {
+    // Set index on the `short_id` field.
+    let short_id_index = IndexModel::builder()
+        .keys(doc! { "short_id": 1 })
+        .options(
+            IndexOptions::builder()
+                .name("short_id_1".to_string())
+                .unique(true)
+                .build(),
+        )
+        .build();
+    // Set index on the `full_url` field for reverse lookups.
+    let full_url_index = IndexModel::builder()
+        .keys(doc! { "full_url": 1 })
+        .options(
+            IndexOptions::builder()
+                .name("full_url_1".to_string())
+                .build(),
+        )
+        .build();
+    // Allow only one canonical URL per full URL for `get_or_create`.
+    let canonical_index = IndexModel::builder()
+        .keys(doc! { "full_url": 1, "canonical": 1 })
+        .options(
+            IndexOptions::builder()
+                .name("full_url_1_canonical_1".to_string())
+                .unique(true)
+                .partial_filter_expression(doc! { "canonical": true })
+                .build(),
+        )
+        .build();
+
+    // Set a wildcard index on the labels for `list_urls_by_tag`, since the
+    // keys of the labels are arbitrary.
+    let metadata_index = IndexModel::builder()
+        .keys(doc! { "metadata.$**": 1 })
+        .options(
+            IndexOptions::builder()
+                .name("metadata.$**_1".to_string())
+                .build(),
+        )
+        .build();
+
+    [
+        short_id_index,
+        full_url_index,
+        canonical_index,
+        metadata_index,
+    ]
+}
+
+/// Checks if the provided existing index enforces the uniqueness of the
+/// provided required index, or is not on the same keys. Otherwise the
+/// existing index would be reported as present without enforcing the
+/// uniqueness the library relies on.
+fn enforces_uniqueness_of(
+    existing: &IndexModel,
+    required: &IndexModel,
+) -> bool {
+    let unique = |index: &IndexModel| {
+        index.options.as_ref().and_then(|options| options.unique) == Some(true)
+    };
+    let partial_filter = |index: &IndexModel| {
+        index
+            .options
+            .as_ref()
+            .and_then(|options| options.partial_filter_expression.clone())
+    };
+
+    existing.keys != required.keys
+        || !unique(required)
+        || (unique(existing)
+            && partial_filter(existing) == partial_filter(required))
+}
+
+/// Gets the name of the provided index.
+fn index_name(index: &IndexModel) -> String {
+    index
+        .options
+        .as_ref()
+        .and_then(|options| options.name.clone())
+        .unwrap_or_default()
+}
+
+/// Lists the indexes of the provided collection. A collection that does not
+/// exist yet has no indexes.
+async fn list_indexes(
+    collection: &Collection<UrlModel>,
+) -> Result<Vec<IndexModel>, ShortyError> {
+    let cursor = match collection.list_indexes(None).await {
+        Ok(cursor) => cursor,
+        Err(err) if is_namespace_not_found(&err) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(cursor.try_collect().await?)
+}
+
+/// Checks if the provided error was returned because the collection does not
+/// exist.
+fn is_namespace_not_found(err: &Error) -> bool {
+    matches!(
+        *err.kind,
+        ErrorKind::Command(ref command_error)
+            if command_error.code == NAMESPACE_NOT_FOUND_CODE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::{bson::doc, options::IndexOptions, IndexModel};
+
+    use super::{enforces_uniqueness_of, required_indexes};
+
+    /// Creates an existing index on the canonical full URLs with the provided
+    /// options.
+    fn canonical_index(options: IndexOptions) -> IndexModel {
+        IndexModel::builder()
+            .keys(doc! { "full_url": 1, "canonical": 1 })
+            .options(options)
+            .build()
+    }
+
+    /// An index on the keys of a unique index must be rejected unless it is
+    /// unique with the same partial filter.
+    #[test]
+    fn mismatched_unique_index_rejected() {
+        let [_, _, required, _] = required_indexes();
+
+        let non_unique = canonical_index(
+            IndexOptions::builder()
+                .partial_filter_expression(doc! { "canonical": true })
+                .build(),
+        );
+        let unfiltered =
+            canonical_index(IndexOptions::builder().unique(true).build());
+        let other_filter = canonical_index(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "canonical": false })
+                .build(),
+        );
+        let matching = canonical_index(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "canonical": true })
+                .build(),
+        );
+
+        assert!(!enforces_uniqueness_of(&non_unique, &required));
+        assert!(!enforces_uniqueness_of(&unfiltered, &required));
+        assert!(!enforces_uniqueness_of(&other_filter, &required));
+        assert!(enforces_uniqueness_of(&matching, &required));
+    }
+
+    /// Indexes on other keys or matching non-unique required indexes must be
+    /// accepted.
+    #[test]
+    fn unrelated_index_accepted() {
+        let [short_id, full_url, required, _] = required_indexes();
+
+        assert!(enforces_uniqueness_of(&full_url, &required));
+        assert!(enforces_uniqueness_of(&short_id, &full_url));
+    }
+}
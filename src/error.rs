@@ -0,0 +1,116 @@
+use std::{error::Error, fmt};
+
+#[cfg(feature = "resolve")]
+use crate::resolve::ResolveError;
+use crate::{id::IdError, metrics};
+
+/// Errors returned by the library.
+#[derive(Debug)]
+pub enum ShortyError {
+    /// An error occurred at the DB layer. The variant stores the underlying
+    /// error returned by MongoDB.
+    Database(mongodb::error::Error),
+    /// A unique short ID could not be generated for the URL.
+    IdGeneration,
+    /// The provided URL is not a valid HTTP or HTTPS URL.
+    InvalidUrl,
+    /// The provided URL points at the base URL of the shortener, which would
+    /// redirect to another short link.
+    SelfReference,
+    /// The provided short ID does not conform to the constraints of the short
+    /// IDs. The variant stores the reason the short ID is invalid.
+    InvalidId(IdError),
+    /// The provided short ID is already used by another URL.
+    IdTaken,
+    /// The provided metadata key is empty, contains a `.` or starts with a
+    /// `$`. The variant stores the key.
+    InvalidMetadataKey(String),
+    /// Deduplication was combined with an option it cannot honour when it
+    /// reuses an existing URL. The variant stores the name of the option.
+    ConflictingOptions(&'static str),
+    /// An index with the keys of a unique index required by the library, e.g.
+    /// the one on the short IDs, exists in the DB but is not unique or does
+    /// not have the required partial filter. The variant stores the name of
+    /// the index, which must be dropped so that [`setup_db`](crate::setup_db)
+    /// can create the required one.
+    NonUniqueIndex(String),
+    /// The library was used before it was configured with
+    /// [`init`](crate::init) or [`setup_db`](crate::setup_db).
+    NotInitialized,
+    /// An environment variable required to configure the library is not set.
+    /// The variant stores the name of the environment variable.
+    MissingEnv(&'static str),
+    /// The redirects of the provided URL could not be resolved. The variant
+    /// stores the reason the resolution failed.
+    #[cfg(feature = "resolve")]
+    Resolve(ResolveError),
+}
+
+impl fmt::Display for ShortyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database(err) => write!(f, "database error: {err}"),
+            Self::IdGeneration => write!(f, "could not generate a unique ID"),
+            Self::InvalidUrl => write!(f, "invalid URL format"),
+            Self::SelfReference => {
+                write!(f, "URL points at the domain of the shortener")
+            }
+            Self::InvalidId(err) => write!(f, "invalid short ID: {err}"),
+            Self::IdTaken => write!(f, "short ID is already taken"),
+            Self::InvalidMetadataKey(key) => {
+                write!(f, "invalid metadata key {key:?}")
+            }
+            Self::ConflictingOptions(option) => {
+                write!(f, "`deduplicate` cannot be combined with `{option}`")
+            }
+            Self::NonUniqueIndex(name) => {
+                write!(
+                    f,
+                    "index {name} does not enforce the required uniqueness"
+                )
+            }
+            Self::NotInitialized => write!(
+                f,
+                "library is not initialised, call `init` or `setup_db` first"
+            ),
+            Self::MissingEnv(name) => {
+                write!(f, "environment variable {name} is not set")
+            }
+            #[cfg(feature = "resolve")]
+            Self::Resolve(err) => write!(f, "could not resolve URL: {err}"),
+        }
+    }
+}
+
+impl Error for ShortyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Database(err) => Some(err),
+            Self::InvalidId(err) => Some(err),
+            #[cfg(feature = "resolve")]
+            Self::Resolve(err) => Some(err),
+            Self::IdGeneration
+            | Self::InvalidUrl
+            | Self::SelfReference
+            | Self::IdTaken
+            | Self::InvalidMetadataKey(_)
+            | Self::ConflictingOptions(_)
+            | Self::NonUniqueIndex(_)
+            | Self::NotInitialized
+            | Self::MissingEnv(_) => None,
+        }
+    }
+}
+
+impl From<IdError> for ShortyError {
+    fn from(value: IdError) -> Self {
+        Self::InvalidId(value)
+    }
+}
+
+impl From<mongodb::error::Error> for ShortyError {
+    fn from(value: mongodb::error::Error) -> Self {
+        metrics::record_db_error();
+        Self::Database(value)
+    }
+}
@@ -1,49 +1,345 @@
-use rand::Rng;
-use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fmt,
+    sync::{Arc, Mutex},
+};
 
-/// Generates an ID for the URL using the URL as a hash for the ID.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Minimum number of characters in a short ID.
+pub const MIN_ID_LENGTH: usize = 1;
+/// Maximum number of characters in a short ID.
+pub const MAX_ID_LENGTH: usize = 32;
+/// Number of characters generated for a short ID by default, excluding the
+/// prefix.
+pub(crate) const DEFAULT_ID_LENGTH: usize = 10;
+/// The characters the generated short IDs are made of.
+const ALPHABET: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Reasons a short ID can be invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdError {
+    /// The short ID has fewer than [`MIN_ID_LENGTH`] characters.
+    TooShort,
+    /// The short ID has more than [`MAX_ID_LENGTH`] characters.
+    TooLong,
+    /// The short ID contains a character that is not an ASCII letter or digit.
+    /// The variant stores the first such character.
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort => {
+                write!(f, "must have at least {MIN_ID_LENGTH} characters")
+            }
+            Self::TooLong => {
+                write!(f, "must have at most {MAX_ID_LENGTH} characters")
+            }
+            Self::InvalidCharacter(ch) => {
+                write!(f, "contains the invalid character {ch:?}")
+            }
+        }
+    }
+}
+
+impl Error for IdError {}
+
+/// Generates the short IDs of a configuration. Every clone of an
+/// `IdGenerator` shares the same random number generator.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdGenerator {
+    /// The seeded random number generator. The random number generator of the
+    /// current thread is used if unset.
+    seeded: Option<Arc<Mutex<StdRng>>>,
+}
+
+impl IdGenerator {
+    /// Creates a generator of the same sequence of short IDs for the same
+    /// seed.
+    pub(crate) fn seeded(seed: u64) -> Self {
+        IdGenerator {
+            seeded: Some(Arc::new(Mutex::new(StdRng::seed_from_u64(seed)))),
+        }
+    }
+
+    /// Generates the next short ID, see [`generate_id`].
+    pub(crate) fn generate(
+        &self,
+        prefix: Option<&str>,
+        length: usize,
+    ) -> String {
+        match &self.seeded {
+            Some(rng_gen) => {
+                let mut rng_gen =
+                    rng_gen.lock().expect("ID generator lock was poisoned");
+                generate_id(&mut *rng_gen, prefix, length)
+            }
+            None => generate_id(&mut rand::thread_rng(), prefix, length),
+        }
+    }
+}
+
+/// Generates an ID of `length` characters picked uniformly at random from the
+/// ASCII letters and digits with the provided random number generator. The
+/// prefix, if provided, is prepended to the generated ID.
+///
+/// The ID does not depend on the URL, so shortening the same URL twice yields
+/// unrelated IDs. See [`collision_probability`] for the chance of generating an
+/// ID that is already taken.
 ///
 /// # Examples
 ///
 /// ```rust,ignore
-/// let id = generate_id("https://example.com");
+/// let mut rng_gen = rand::thread_rng();
+/// let id = generate_id(&mut rng_gen, None, DEFAULT_ID_LENGTH);
+/// let typed_id = generate_id(&mut rng_gen, Some("u"), 8);
 /// ```
-pub(crate) fn generate_id(url: &str) -> String {
-    const NUM_CHARS: usize = 10;
+pub(crate) fn generate_id(
+    rng_gen: &mut impl Rng,
+    prefix: Option<&str>,
+    length: usize,
+) -> String {
+    let mut short_id = prefix.unwrap_or_default().to_string();
+    short_id.extend(
+        (0..length).map(|_| {
+            char::from(ALPHABET[rng_gen.gen_range(0..ALPHABET.len())])
+        }),
+    );
+    short_id
+}
 
-    let mut url_hasher = Sha256::new();
-    url_hasher.update(url.as_bytes());
-    let mut url_hash = hex::encode(url_hasher.finalize());
+/// Calculates the probability that at least 2 of `num_ids` short IDs of
+/// `length` generated characters are the same, using the birthday
+/// approximation.
+///
+/// A collision is not an error: a new short ID is generated when the generated
+/// one is already taken. The probability tells how often that retry happens
+/// and when to pick a longer length.
+///
+/// | Length | Possible IDs | IDs for a 1% chance of a collision |
+/// | ------ | ------------ | ---------------------------------- |
+/// | 4      | 1.5 × 10⁷    | 545                                |
+/// | 5      | 9.2 × 10⁸    | 4,290                              |
+/// | 6      | 5.7 × 10¹⁰   | 33,800                             |
+/// | 7      | 3.5 × 10¹²   | 266,000                            |
+/// | 8      | 2.2 × 10¹⁴   | 2.1 million                        |
+/// | 9      | 1.4 × 10¹⁶   | 16.5 million                       |
+/// | 10     | 8.4 × 10¹⁷   | 130 million                        |
+/// | 11     | 5.2 × 10¹⁹   | 1 billion                          |
+/// | 12     | 3.2 × 10²¹   | 8 billion                          |
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::collision_probability;
+/// #
+/// // The chance of a collision among a million IDs of the default length.
+/// assert!(collision_probability(10, 1_000_000) < 1e-6);
+/// ```
+pub fn collision_probability(length: usize, num_ids: u64) -> f64 {
+    let num_possible_ids = (ALPHABET.len() as f64).powf(length as f64);
+    let num_ids = num_ids as f64;
+    let num_pairs = num_ids * (num_ids - 1.0).max(0.0) / 2.0;
 
-    let mut rng_gen = rand::thread_rng();
+    -(-num_pairs / num_possible_ids).exp_m1()
+}
 
-    let short_id_start_idx: usize =
-        rng_gen.gen_range(0..(url_hash.len() - NUM_CHARS));
-    let short_id_end_idx = short_id_start_idx + NUM_CHARS;
+/// Checks if the short IDs generated with the provided prefix and length pass
+/// [`validate_id`].
+///
+/// The prefix counts toward the length of the short ID. Since the prefix may
+/// only contain ASCII letters and digits, a prefixed ID is always a single
+/// path segment and cannot shadow any other route of the server.
+///
+/// # Errors
+///
+/// The [`IdError`] describing why the generated short IDs would be invalid.
+pub(crate) fn validate_generated_id(
+    prefix: Option<&str>,
+    length: usize,
+) -> Result<(), IdError> {
+    if prefix == Some("") {
+        return Err(IdError::TooShort);
+    }
 
-    // Make random alphabets in the short ID range uppercase.
-    unsafe {
-        for ch in url_hash.as_bytes_mut() {
-            if rng_gen.gen_bool(0.30) && ch.is_ascii_alphabetic() {
-                ch.make_ascii_uppercase();
-            }
-        }
+    let prefix = prefix.unwrap_or_default();
+    validate_id(&format!("{prefix}{}", "0".repeat(length)))
+}
+
+/// Checks if the provided short ID conforms to the constraints of the
+/// generated short IDs, i.e. it has between [`MIN_ID_LENGTH`] and
+/// [`MAX_ID_LENGTH`] characters and only contains ASCII letters and digits.
+///
+/// # Errors
+///
+/// The [`IdError`] describing why the short ID is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::{validate_id, IdError};
+/// #
+/// assert!(validate_id("abcdAbc123").is_ok());
+/// assert_eq!(validate_id("my/id"), Err(IdError::InvalidCharacter('/')));
+/// ```
+pub fn validate_id(id: &str) -> Result<(), IdError> {
+    let num_chars = id.chars().count();
+    if num_chars < MIN_ID_LENGTH {
+        return Err(IdError::TooShort);
+    }
+    if num_chars > MAX_ID_LENGTH {
+        return Err(IdError::TooLong);
     }
 
-    url_hash[short_id_start_idx..short_id_end_idx].to_string()
+    match id.chars().find(|ch| !ch.is_ascii_alphanumeric()) {
+        Some(ch) => Err(IdError::InvalidCharacter(ch)),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
     /// The IDs generated by 2 consecutive calls to the
-    /// [`generate_id`](super::generate_id) function must not be the same.
+    /// [`generate`](IdGenerator::generate) function must not be the same.
     #[test]
     fn id_not_same() {
-        const URL: &str = "https://example.com";
-
-        let first_id = super::generate_id(URL);
-        let second_id = super::generate_id(URL);
+        let first_id = IdGenerator::default().generate(None, DEFAULT_ID_LENGTH);
+        let second_id =
+            IdGenerator::default().generate(None, DEFAULT_ID_LENGTH);
 
         assert_ne!(first_id, second_id);
     }
+
+    /// The generated IDs must pass their own validation.
+    #[test]
+    fn generated_id_is_valid() {
+        let id = IdGenerator::default().generate(None, DEFAULT_ID_LENGTH);
+
+        assert_eq!(validate_id(&id), Ok(()));
+    }
+
+    /// A large sample of IDs of the default length must not collide.
+    #[test]
+    fn generated_ids_do_not_collide() {
+        const NUM_IDS: usize = 100_000;
+
+        let ids: HashSet<_> = (0..NUM_IDS)
+            .map(|_| IdGenerator::default().generate(None, DEFAULT_ID_LENGTH))
+            .collect();
+
+        assert_eq!(ids.len(), NUM_IDS);
+    }
+
+    /// Every character of the alphabet must appear about as often as the
+    /// others in the generated IDs.
+    #[test]
+    fn generated_characters_uniformly_distributed() {
+        const NUM_IDS: usize = 100_000;
+
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for _ in 0..NUM_IDS {
+            for ch in IdGenerator::default()
+                .generate(None, DEFAULT_ID_LENGTH)
+                .chars()
+            {
+                *counts.entry(ch).or_default() += 1;
+            }
+        }
+
+        let expected = NUM_IDS * DEFAULT_ID_LENGTH / ALPHABET.len();
+        assert_eq!(counts.len(), ALPHABET.len());
+        for (ch, count) in counts {
+            assert!(
+                count.abs_diff(expected) < expected / 10,
+                "{ch:?} was generated {count} times, expected about {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn collision_probability_grows_with_num_ids() {
+        assert_eq!(collision_probability(DEFAULT_ID_LENGTH, 1), 0.0);
+        assert!(collision_probability(DEFAULT_ID_LENGTH, 1_000_000) < 1e-6);
+        assert!(collision_probability(4, 100_000) > 0.99);
+    }
+
+    #[test]
+    fn valid_id_accepted() {
+        assert_eq!(validate_id("sale2023"), Ok(()));
+    }
+
+    #[test]
+    fn id_with_illegal_character_rejected() {
+        assert_eq!(validate_id("my/id"), Err(IdError::InvalidCharacter('/')));
+    }
+
+    #[test]
+    fn empty_id_rejected() {
+        assert_eq!(validate_id(""), Err(IdError::TooShort));
+    }
+
+    #[test]
+    fn over_long_id_rejected() {
+        let id = "a".repeat(MAX_ID_LENGTH + 1);
+
+        assert_eq!(validate_id(&id), Err(IdError::TooLong));
+    }
+
+    /// Generators seeded with the same seed must generate the same sequence
+    /// of short IDs.
+    #[test]
+    fn seeded_ids_reproducible() {
+        let generator = IdGenerator::seeded(42);
+        let ids: Vec<_> = (0..3)
+            .map(|_| generator.generate(None, DEFAULT_ID_LENGTH))
+            .collect();
+
+        assert_eq!(ids, ["WXdP2Pjq80", "vVO8WD0W3e", "qUV3LRnCOe"]);
+
+        let generator = IdGenerator::seeded(42);
+        assert_eq!(generator.generate(None, DEFAULT_ID_LENGTH), ids[0]);
+    }
+
+    #[test]
+    fn generated_id_starts_with_prefix() {
+        let id = IdGenerator::default().generate(Some("u"), 8);
+
+        assert!(id.starts_with('u'));
+        assert_eq!(id.len(), 9);
+    }
+
+    #[test]
+    fn valid_prefix_accepted() {
+        assert_eq!(validate_generated_id(Some("f"), DEFAULT_ID_LENGTH), Ok(()));
+    }
+
+    #[test]
+    fn prefix_with_illegal_character_rejected() {
+        assert_eq!(
+            validate_generated_id(Some("f/"), DEFAULT_ID_LENGTH),
+            Err(IdError::InvalidCharacter('/'))
+        );
+    }
+
+    #[test]
+    fn over_long_prefix_rejected() {
+        let prefix = "a".repeat(MAX_ID_LENGTH - DEFAULT_ID_LENGTH + 1);
+
+        assert_eq!(
+            validate_generated_id(Some(&prefix), DEFAULT_ID_LENGTH),
+            Err(IdError::TooLong)
+        );
+    }
+
+    #[test]
+    fn zero_length_rejected() {
+        assert_eq!(validate_generated_id(None, 0), Err(IdError::TooShort));
+    }
 }
@@ -3,6 +3,13 @@
 //! your desired location. Once created, the URLs are immutable and only the
 //! view count will be updated on every get request.
 //!
+//! # Initialisation
+//!
+//! The library has to be configured with [`init`](init) or
+//! [`setup_db`](setup_db) before it is used. Every function that accesses the
+//! DB returns a [`NotInitialized`](ShortyError::NotInitialized) error
+//! otherwise.
+//!
 //! # Usage
 //!
 //! ```rust,no_run
@@ -38,19 +45,95 @@
 //! }
 //! ```
 
+mod cache;
+mod config;
+mod connection;
 mod db;
+mod error;
 mod id;
+pub mod metrics;
+mod options;
+#[cfg(feature = "resolve")]
+mod resolve;
+#[cfg(feature = "server")]
+pub mod server;
+mod shortener;
+mod trace;
+mod url_utils;
+mod view_counts;
+
+use std::{collections::BTreeMap, time::Duration};
+
+use futures::{stream, Stream, TryStreamExt};
+use serde::Serialize;
+
+use crate::{
+    config::ViewCountBatching,
+    db::urls::Url,
+    id::{validate_generated_id, DEFAULT_ID_LENGTH},
+    url_utils::{has_same_host, validate_url, with_default_scheme},
+};
+
+pub use crate::{
+    config::Config,
+    connection::MongoDbConnection,
+    db::SetupReport,
+    error::ShortyError,
+    id::{
+        collision_probability, validate_id, IdError, MAX_ID_LENGTH,
+        MIN_ID_LENGTH,
+    },
+    options::{CreateOptions, CreateOptionsBuilder},
+    shortener::{MongoShortener, Shortener},
+    url_utils::{encode_location, normalize_url},
+};
+
+#[cfg(feature = "resolve")]
+pub use crate::resolve::ResolveError;
+
+/// Sets the configuration used by the library, replacing any configuration
+/// set earlier. Either this function or [`setup_db`](setup_db) must be called
+/// before using the rest of the library.
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::{init, Config};
+/// #
+/// init(Config::new("mongodb://localhost:27017", "shorty"));
+/// ```
+pub fn init(config: Config) {
+    config::set(config);
+}
 
-use crate::{db::urls::Url, id::generate_id};
+/// Number of short IDs generated for a URL before giving up on finding a
+/// unique one.
+const ID_GENERATION_ATTEMPTS: usize = 5;
 
 /// Sets up the database required for the library.
 ///
-/// Sets up the URL collection where the shortened URL are stored. Call this
-/// function atleast once before saving any URLs.
+/// Sets up the URL collection where the shortened URL are stored. The indexes
+/// are also set up lazily the first time a URL is created, but calling this
+/// function upfront surfaces setup errors early.
+///
+/// If the library was not configured with [`init`](init), it is configured
+/// from the environment variables with [`Config::from_env`](Config::from_env).
+///
+/// The function can be called any number of times. Indexes that already exist
+/// are left untouched.
+///
+/// # Returns
+///
+/// The indexes created by the setup and the ones that already existed.
 ///
 /// # Errors
 ///
-/// A description of the error from MongoDB if the setup could not be performed.
+/// A [`MissingEnv`](ShortyError::MissingEnv) error if the library was not
+/// configured and the environment variables are not set, a
+/// [`NonUniqueIndex`](ShortyError::NonUniqueIndex) error if an existing index
+/// does not enforce the uniqueness of a required unique index or a
+/// [`Database`](ShortyError::Database) error if the setup could not be
+/// performed.
 ///
 /// # Examples
 ///
@@ -64,12 +147,12 @@ use crate::{db::urls::Url, id::generate_id};
 /// }
 /// # }
 /// ```
-pub async fn setup_db() -> Result<(), String> {
-    let setup_result = Url::setup().await;
-    match setup_result {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.to_string()),
+pub async fn setup_db() -> Result<SetupReport, ShortyError> {
+    if !config::is_set() {
+        init(Config::from_env()?);
     }
+
+    Url::setup(&config::get()?).await
 }
 
 /// Creates a shortened URL for the provided full URL.
@@ -80,58 +163,685 @@ pub async fn setup_db() -> Result<(), String> {
 ///
 /// # Errors
 ///
-/// Returns an error if a unique ID could not be generated for the full URL
+/// Returns an [`InvalidUrl`](ShortyError::InvalidUrl) error if the full URL is
+/// not a valid HTTP or HTTPS URL, a
+/// [`SelfReference`](ShortyError::SelfReference) error if it points at the
+/// host of [`Config::base_url`](Config::base_url), an
+/// [`IdGeneration`](ShortyError::IdGeneration) error if a unique ID could not
+/// be generated for the full URL or a [`Database`](ShortyError::Database)
+/// error if the DB could not be reached. With the `resolve` feature, returns a
+/// `Resolve` error if the redirects of the full URL could not be resolved.
 ///
 /// # Examples
 /// ```rust,no_run
-/// # use shorty::create_url;
+/// # use shorty::{create_url, ShortyError};
 /// #
 /// # #[tokio::main]
-/// # async fn main() -> Result<(), &'static str> {
+/// # async fn main() -> Result<(), ShortyError> {
 /// let short_id = create_url("https://example.com").await?;
 /// # Ok(())
 /// # }
 /// ```
-pub async fn create_url(full_url: &str) -> Result<String, &'static str> {
-    const SAVE_RETRY_COUNT: u8 = 2;
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = tracing::field::Empty))
+)]
+pub async fn create_url(full_url: &str) -> Result<String, ShortyError> {
+    let outcome = create_with_options(
+        &config::get()?,
+        full_url,
+        CreateOptions::default(),
+    )
+    .await?;
+    Ok(outcome.short_id)
+}
+
+/// The outcome of shortening a URL with
+/// [`create_url_with_options`](create_url_with_options) or
+/// [`create_url_deduplicated`](create_url_deduplicated).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateOutcome {
+    /// The short ID of the URL.
+    pub short_id: String,
+    /// Set if a new short ID was created for the URL. Unset if the URL was
+    /// already shortened and the existing short ID was returned.
+    pub created: bool,
+}
+
+/// Creates a shortened URL for the provided full URL with the provided
+/// options. Use [`create_url`](create_url) if the default options suffice.
+///
+/// # Returns
+///
+/// The short ID for the full URL along with whether it was newly created.
+/// The short ID is only reused if deduplication is enabled with
+/// [`CreateOptionsBuilder::deduplicate`](CreateOptionsBuilder::deduplicate).
+///
+/// # Errors
+///
+/// Returns an [`InvalidId`](ShortyError::InvalidId) error if the vanity short
+/// ID or the generated short IDs would not pass [`validate_id`](validate_id)
+/// and an [`IdTaken`](ShortyError::IdTaken) error if the vanity short ID is
+/// already used. Otherwise returns the same errors as
+/// [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use shorty::{create_url_with_options, CreateOptions, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let options = CreateOptions::new()
+///     .vanity("sale")
+///     .expiry(Duration::from_secs(7 * 24 * 60 * 60))
+///     .build();
+/// let outcome = create_url_with_options("https://example.com", options).await?;
+/// assert_eq!(outcome.short_id, "sale");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = tracing::field::Empty))
+)]
+pub async fn create_url_with_options(
+    full_url: &str,
+    options: CreateOptions,
+) -> Result<CreateOutcome, ShortyError> {
+    create_with_options(&config::get()?, full_url, options).await
+}
+
+/// Creates a shortened URL for the provided full URL with the provided
+/// options. Shared by all the functions creating URLs.
+async fn create_with_options(
+    config: &Config,
+    full_url: &str,
+    options: CreateOptions,
+) -> Result<CreateOutcome, ShortyError> {
+    if let Some(option) = options.get_conflicting_option() {
+        return Err(ShortyError::ConflictingOptions(option));
+    }
+
+    match options.get_vanity() {
+        Some(short_id) => validate_id(short_id)?,
+        None => {
+            validate_generated_id(options.get_prefix(), options.get_length())?
+        }
+    }
+
+    for key in options.get_metadata().keys() {
+        validate_metadata_key(key)?;
+    }
+
+    let full_url = apply_default_scheme(config, full_url);
+    let full_url = if options.is_normalizing() {
+        normalize_url(&full_url)?
+    } else {
+        full_url
+    };
+    let full_url = prepare_full_url(config, &full_url).await?;
+
+    if options.is_deduplicating() {
+        // URLs that expire or have a maximum number of views are not reused,
+        // since they may stop resolving.
+        if let Some(url_object) =
+            Url::fetch_unrestricted_by_full_url(config, &full_url).await?
+        {
+            trace::record_short_id(url_object.get_short_id());
+            return Ok(CreateOutcome {
+                short_id: url_object.get_short_id().to_string(),
+                created: false,
+            });
+        }
+    }
+
+    Url::ensure_indexes(config).await?;
+    let short_id = match options.get_vanity() {
+        Some(short_id) => {
+            save_with_id(config, &full_url, short_id, &options).await?
+        }
+        None => save_with_generated_id(config, &full_url, &options).await?,
+    };
+
+    metrics::record_url_created();
+    trace::record_short_id(&short_id);
+    Ok(CreateOutcome {
+        short_id,
+        created: true,
+    })
+}
+
+/// Creates a shortened URL for the provided full URL that stops resolving once
+/// it was fetched `max_views` times. [`get_url`](get_url) returns
+/// [`None`](std::option::Option::None) for the short ID from then on.
+///
+/// The view count of a capped URL is always tracked, irrespective of
+/// [`Config::track_views`](Config::track_views), and is checked and
+/// incremented atomically, so concurrent requests cannot fetch the URL more
+/// than `max_views` times.
+///
+/// # Returns
+///
+/// The generated short ID for the full URL.
+///
+/// # Errors
+///
+/// Returns the same errors as [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{create_url_with_max_views, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let short_id = create_url_with_max_views("https://example.com", 100).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = tracing::field::Empty))
+)]
+pub async fn create_url_with_max_views(
+    full_url: &str,
+    max_views: u32,
+) -> Result<String, ShortyError> {
+    let options = CreateOptions::builder().max_views(max_views).build();
+    Ok(create_with_options(&config::get()?, full_url, options)
+        .await?
+        .short_id)
+}
+
+/// Creates a shortened URL for the provided full URL with a short ID starting
+/// with the provided prefix. Prefixes can be used to distinguish the types of
+/// links, e.g. `f` for files and `u` for URLs.
+///
+/// The prefix is stored as part of the short ID, so the URL is fetched with
+/// the full prefixed ID. The prefix counts toward [`MAX_ID_LENGTH`] and may
+/// only contain ASCII letters and digits.
+///
+/// # Returns
+///
+/// The generated short ID for the full URL, including the prefix.
+///
+/// # Errors
+///
+/// Returns an [`InvalidId`](ShortyError::InvalidId) error if the prefix is
+/// empty, too long or contains an invalid character. Otherwise returns the
+/// same errors as [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{create_url_with_prefix, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let short_id = create_url_with_prefix("https://example.com", "u").await?;
+/// assert!(short_id.starts_with('u'));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = tracing::field::Empty))
+)]
+pub async fn create_url_with_prefix(
+    full_url: &str,
+    prefix: &str,
+) -> Result<String, ShortyError> {
+    let options = CreateOptions::builder().prefix(prefix).build();
+    Ok(create_with_options(&config::get()?, full_url, options)
+        .await?
+        .short_id)
+}
+
+/// Prepends the scheme set with
+/// [`Config::default_scheme`](Config::default_scheme) to the provided full URL
+/// if it is a bare host.
+fn apply_default_scheme(config: &Config, full_url: &str) -> String {
+    match config.get_default_scheme() {
+        Some(scheme) => with_default_scheme(full_url, scheme),
+        None => full_url.to_string(),
+    }
+}
+
+/// Validates the provided full URL and resolves its redirects if enabled with
+/// `Config::resolve_redirects`. Both the provided and the resolved URLs must
+/// not point at the base URL set with [`Config::base_url`](Config::base_url).
+///
+/// # Returns
+///
+/// The full URL that must be stored.
+async fn prepare_full_url(
+    config: &Config,
+    full_url: &str,
+) -> Result<String, ShortyError> {
+    validate_url(full_url)?;
+    check_self_reference(config, full_url)?;
+
+    #[cfg(feature = "resolve")]
+    if let Some(max_hops) = config.get_max_redirect_hops() {
+        let resolved_url = resolve::resolve_redirects(
+            full_url,
+            max_hops,
+            config.get_resolve_timeout(),
+        )
+        .await?;
+        check_self_reference(config, &resolved_url)?;
+        return Ok(resolved_url);
+    }
+
+    Ok(full_url.to_string())
+}
+
+/// Checks that the provided full URL does not point at the configured base
+/// URL of the shortener.
+fn check_self_reference(
+    config: &Config,
+    full_url: &str,
+) -> Result<(), ShortyError> {
+    match config.get_base_url() {
+        Some(base_url) if has_same_host(full_url, base_url) => {
+            Err(ShortyError::SelfReference)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the provided metadata key can be used as the name of a field of
+/// the metadata subdocument.
+fn validate_metadata_key(key: &str) -> Result<(), ShortyError> {
+    if key.is_empty() || key.contains('.') || key.starts_with('$') {
+        return Err(ShortyError::InvalidMetadataKey(key.to_string()));
+    }
+    Ok(())
+}
+
+/// Saves the provided full URL against the provided short ID.
+///
+/// # Returns
+///
+/// The short ID for the full URL.
+async fn save_with_id(
+    config: &Config,
+    full_url: &str,
+    short_id: &str,
+    options: &CreateOptions,
+) -> Result<String, ShortyError> {
+    if Url::fetch_url(config, short_id).await?.is_some() {
+        return Err(ShortyError::IdTaken);
+    }
+
+    let mut url_obj = Url::new(config, short_id.to_string(), full_url, 0)
+        .await?
+        .with_max_views(options.get_max_views())
+        .with_expiry(options.get_expiry())
+        .with_metadata(options.get_metadata().clone());
+    url_obj.save().await?;
+
+    Ok(url_obj.get_short_id().to_string())
+}
+
+/// Saves the provided full URL against a newly generated short ID.
+///
+/// # Returns
+///
+/// The generated short ID for the full URL.
+async fn save_with_generated_id(
+    config: &Config,
+    full_url: &str,
+    options: &CreateOptions,
+) -> Result<String, ShortyError> {
+    let prefix = options.get_prefix();
+    let base_length = options.get_length();
+    let (attempts_per_length, max_length) = match config.get_id_length_growth()
+    {
+        Some(growth) => {
+            validate_generated_id(prefix, growth.max_length)?;
+            (
+                usize::from(growth.collisions.max(1)),
+                growth.max_length.max(base_length),
+            )
+        }
+        None => (ID_GENERATION_ATTEMPTS, base_length),
+    };
+    let attempts = attempts_per_length * (max_length - base_length + 1);
+
+    let id = config.get_id_generator().generate(prefix, base_length);
+    let mut url_obj = Url::new(config, id, full_url, 0)
+        .await?
+        .with_max_views(options.get_max_views())
+        .with_expiry(options.get_expiry())
+        .with_metadata(options.get_metadata().clone());
+
+    let mut length = base_length;
+    let mut collisions = 0;
+    let next_id = || {
+        // Grow the short ID once it collided too often at the current length.
+        collisions += 1;
+        if collisions == attempts_per_length {
+            collisions = 0;
+            length += 1;
+        }
+        config.get_id_generator().generate(prefix, length)
+    };
+
+    url_obj.save_with_retries(attempts, next_id).await?;
+    Ok(url_obj.get_short_id().to_string())
+}
+
+/// Performs the checks of [`create_url`](create_url) without saving anything.
+///
+/// The full URL is validated and short IDs are generated until one that is not
+/// already used is found. Since nothing is saved, the returned ID may be taken
+/// by another URL before it is actually created.
+///
+/// # Returns
+///
+/// The candidate short ID for the full URL.
+///
+/// # Errors
+///
+/// Returns the same errors as [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{create_url_dry_run, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let candidate_id = create_url_dry_run("https://example.com").await?;
+/// println!("would be shortened to {candidate_id}");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn create_url_dry_run(full_url: &str) -> Result<String, ShortyError> {
+    let config = config::get()?;
+    let full_url = apply_default_scheme(&config, full_url);
+    prepare_full_url(&config, &full_url).await?;
 
-    let id = generate_id(full_url);
-    let mut url_obj = Url::new(id, full_url, 0).await;
+    for _ in 0..ID_GENERATION_ATTEMPTS {
+        let id = config.get_id_generator().generate(None, DEFAULT_ID_LENGTH);
+        if !exists(&id).await? {
+            return Ok(id);
+        }
+    }
 
-    for _ in 1..SAVE_RETRY_COUNT {
+    Err(ShortyError::IdGeneration)
+}
+
+/// Checks if a URL is stored against the provided short ID.
+///
+/// # Errors
+///
+/// Returns a [`Database`](ShortyError::Database) error if an error occurs at
+/// the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{exists, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// if exists("abcd1234").await? {
+///     println!("short ID is taken");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn exists(short_id: &str) -> Result<bool, ShortyError> {
+    Ok(Url::fetch_url(&config::get()?, short_id).await?.is_some())
+}
+
+/// Creates a shortened URL for the provided full URL with the provided vanity
+/// short ID instead of a generated one.
+///
+/// # Returns
+///
+/// The short ID for the full URL.
+///
+/// # Errors
+///
+/// Returns an [`InvalidUrl`](ShortyError::InvalidUrl) error if the full URL is
+/// not a valid HTTP or HTTPS URL, an [`InvalidId`](ShortyError::InvalidId)
+/// error if the short ID does not pass [`validate_id`](validate_id), an
+/// [`IdTaken`](ShortyError::IdTaken) error if the short ID is already used or
+/// a [`Database`](ShortyError::Database) error if the DB could not be reached.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{create_url_with_id, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let short_id = create_url_with_id("https://example.com", "sale").await?;
+/// assert_eq!(short_id, "sale");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn create_url_with_id(
+    full_url: &str,
+    short_id: &str,
+) -> Result<String, ShortyError> {
+    let options = CreateOptions::builder().vanity(short_id).build();
+    Ok(create_with_options(&config::get()?, full_url, options)
+        .await?
+        .short_id)
+}
+
+/// Creates a shortened URL for the provided full URL unless it was already
+/// shortened, in which case the existing short ID is returned. Only short IDs
+/// that always resolve are reused, URLs created with an expiry or a maximum
+/// number of views are ignored.
+///
+/// # Returns
+///
+/// The short ID for the full URL along with whether it was newly created.
+///
+/// # Errors
+///
+/// Returns the same errors as [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{create_url_deduplicated, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let outcome = create_url_deduplicated("https://example.com").await?;
+/// if !outcome.created {
+///     println!("already shortened to {}", outcome.short_id);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn create_url_deduplicated(
+    full_url: &str,
+) -> Result<CreateOutcome, ShortyError> {
+    let options = CreateOptions::builder().deduplicate(true).build();
+    create_with_options(&config::get()?, full_url, options).await
+}
+
+/// Gets the short ID of the provided full URL, shortening it if it was never
+/// shortened before.
+///
+/// Only short IDs that always resolve are reused. URLs created with an expiry
+/// or a maximum number of views are ignored, since they may stop resolving.
+///
+/// Unlike [`create_url_deduplicated`](create_url_deduplicated), concurrent
+/// calls for the same full URL never create more than one short ID. The URL
+/// created by this function is unique per full URL in the DB, so a call
+/// losing the race to create it returns the short ID created by the winner.
+///
+/// # Returns
+///
+/// The short ID for the full URL.
+///
+/// # Errors
+///
+/// Returns the same errors as [`create_url`](create_url).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{get_or_create, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let short_id = get_or_create("https://example.com").await?;
+/// assert_eq!(get_or_create("https://example.com").await?, short_id);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = tracing::field::Empty))
+)]
+pub async fn get_or_create(full_url: &str) -> Result<String, ShortyError> {
+    let config = config::get()?;
+    let full_url = apply_default_scheme(&config, full_url);
+    let full_url = prepare_full_url(&config, &full_url).await?;
+    Url::ensure_indexes(&config).await?;
+
+    for _ in 0..ID_GENERATION_ATTEMPTS {
+        // URLs that expire or have a maximum number of views are not reused,
+        // since they may stop resolving.
+        if let Some(url_object) =
+            Url::fetch_unrestricted_by_full_url(&config, &full_url).await?
+        {
+            trace::record_short_id(url_object.get_short_id());
+            return Ok(url_object.get_short_id().to_string());
+        }
+
+        let id = config.get_id_generator().generate(None, DEFAULT_ID_LENGTH);
+        let mut url_obj =
+            Url::new(&config, id, &full_url, 0).await?.into_canonical();
         match url_obj.save().await {
-            Ok(_) => return Ok(url_obj.get_short_id().to_string()),
-            Err(err) => {
-                // An error should only really occur when the generated ID is
-                // already present in the DB.
-                println!("{err:#?}");
-                url_obj.update_short_id(generate_id(full_url));
+            Ok(_) => {
+                metrics::record_url_created();
+                trace::record_short_id(url_obj.get_short_id());
+                return Ok(url_obj.get_short_id().to_string());
             }
+            // Either the generated short ID is taken or a concurrent call
+            // created the URL first, which the next attempt finds.
+            Err(ShortyError::IdTaken) => continue,
+            Err(err) => return Err(err),
         }
     }
 
-    Err("could not generate a unique ID")
+    Err(ShortyError::IdGeneration)
+}
+
+/// Deletes the URL stored against the provided short ID.
+///
+/// # Returns
+///
+/// Whether a URL was stored against the short ID.
+///
+/// # Errors
+///
+/// Returns a [`Database`](ShortyError::Database) error if an error occurs at
+/// the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{delete_url, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// if !delete_url("abcd1234").await? {
+///     println!("url not found");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = short_id))
+)]
+pub async fn delete_url(short_id: &str) -> Result<bool, ShortyError> {
+    delete_short_id(&config::get()?, short_id).await
+}
+
+/// Deletes the URL stored against the provided short ID from the backend of
+/// the provided configuration. Shared by [`delete_url`](delete_url) and
+/// [`MongoShortener`](MongoShortener).
+async fn delete_short_id(
+    config: &Config,
+    short_id: &str,
+) -> Result<bool, ShortyError> {
+    let is_deleted = Url::delete(config, short_id).await?;
+    config.get_cache().remove(short_id);
+
+    Ok(is_deleted)
+}
+
+/// Finds the short IDs of all the URLs that were shortened from the provided
+/// full URL.
+///
+/// # Returns
+///
+/// The short IDs pointing at the full URL. The list is empty if the full URL
+/// was never shortened.
+///
+/// # Errors
+///
+/// Returns a [`Database`](ShortyError::Database) error if an error occurs at
+/// the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{find_by_full_url, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// for short_id in find_by_full_url("https://example.com").await? {
+///     println!("{short_id}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn find_by_full_url(
+    full_url: &str,
+) -> Result<Vec<String>, ShortyError> {
+    Url::fetch_short_ids_by_full_url(&config::get()?, full_url).await
 }
 
 /// Gets the full URL stored against the provided short ID and updates it's view
-/// count.
+/// count. The view count is not updated if view tracking is disabled with
+/// [`Config::track_views`](Config::track_views).
 ///
 /// # Returns
 ///
 /// The full URL stored against the short ID otherwise
-/// [`None`](std::option::Option::None).
+/// [`None`](std::option::Option::None). [`None`](std::option::Option::None) is
+/// also returned once a URL created with
+/// [`create_url_with_max_views`](create_url_with_max_views) reached its
+/// maximum number of views or once a URL created with an
+/// [`expiry`](CreateOptionsBuilder::expiry) expired.
 ///
 /// # Errors
 ///
-/// The function will return an error if an error occurs at the DB layer.
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer.
 ///
 /// # Examples
 ///
 /// ```rust,no_run
-/// # use shorty::get_url;
+/// # use shorty::{get_url, ShortyError};
 /// #
 /// # #[tokio::main]
-/// # async fn main() -> Result<(), &'static str> {
+/// # async fn main() -> Result<(), ShortyError> {
 /// let full_url = get_url("abcd1234").await?;
 /// match full_url {
 ///     Some(url) => println!("{url}"),
@@ -140,26 +850,660 @@ pub async fn create_url(full_url: &str) -> Result<String, &'static str> {
 /// # Ok(())
 /// # }
 /// ```
-pub async fn get_url(short_id: &str) -> Result<Option<String>, &'static str> {
-    let url_object = match Url::fetch_url(short_id).await {
-        Ok(obj) => obj,
-        Err(e) => {
-            println!("error while fetching url: {e}");
-            return Err("error while getting url object");
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = short_id))
+)]
+pub async fn get_url(short_id: &str) -> Result<Option<String>, ShortyError> {
+    fetch_full_url(&config::get()?, short_id).await
+}
+
+/// Gets the full URL stored against the provided short ID like
+/// [`get_url`](get_url), along with its view count including the current
+/// view, e.g. to show how many times the URL was viewed.
+///
+/// The view is counted and the updated count is read in a single atomic
+/// operation, so the returned count is exactly the one stored in the DB after
+/// the view. The view is always counted this way, even if views are not
+/// tracked or batched, and the cache is bypassed.
+///
+/// # Errors
+///
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{get_url_and_count, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// if let Some((full_url, view_count)) = get_url_and_count("abcd1234").await? {
+///     println!("{full_url} was viewed {view_count} times");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = short_id))
+)]
+pub async fn get_url_and_count(
+    short_id: &str,
+) -> Result<Option<(String, u32)>, ShortyError> {
+    let config = config::get()?;
+
+    match Url::fetch_and_increment_view_count(&config, short_id).await? {
+        Some(url_object) => {
+            metrics::record_url_hit();
+            Ok(Some((
+                url_object.get_full_url().to_string(),
+                url_object.get_view_count(),
+            )))
+        }
+        None => {
+            metrics::record_url_not_found();
+            Ok(None)
         }
+    }
+}
+
+/// The outcome of [`resolve`](resolve)ing a short ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// The short ID resolves to the full URL stored in the variant.
+    Found(String),
+    /// A URL is stored against the short ID but it no longer resolves, since
+    /// it expired or reached its maximum number of views.
+    Gone,
+    /// No URL is stored against the short ID.
+    NotFound,
+}
+
+impl Resolution {
+    /// Gets the full URL the short ID resolves to, if it was found.
+    pub fn into_full_url(self) -> Option<String> {
+        match self {
+            Self::Found(full_url) => Some(full_url),
+            Self::Gone | Self::NotFound => None,
+        }
+    }
+}
+
+/// Resolves the provided short ID like [`get_url`](get_url), but tells a short
+/// ID that never existed apart from one whose URL expired or reached its
+/// maximum number of views, e.g. to respond with a `410 Gone` instead of a
+/// `404 Not Found`.
+///
+/// # Errors
+///
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{resolve, Resolution, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// match resolve("abcd1234").await? {
+///     Resolution::Found(url) => println!("{url}"),
+///     Resolution::Gone => println!("url is no longer available"),
+///     Resolution::NotFound => println!("url not found"),
+/// };
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(short_id = short_id))
+)]
+pub async fn resolve(short_id: &str) -> Result<Resolution, ShortyError> {
+    resolve_short_id(&config::get()?, short_id).await
+}
+
+/// Gets the full URL stored against the provided short ID from the backend of
+/// the provided configuration and updates its view count. Shared by
+/// [`get_url`](get_url) and [`MongoShortener`](MongoShortener).
+async fn fetch_full_url(
+    config: &Config,
+    short_id: &str,
+) -> Result<Option<String>, ShortyError> {
+    Ok(resolve_short_id(config, short_id).await?.into_full_url())
+}
+
+/// Resolves the provided short ID with the backend of the provided
+/// configuration and updates its view count.
+async fn resolve_short_id(
+    config: &Config,
+    short_id: &str,
+) -> Result<Resolution, ShortyError> {
+    if let Some(full_url) = config.get_cache().get(short_id) {
+        if config.is_tracking_views() {
+            let config = config.clone();
+            let short_id = short_id.to_string();
+            // The view is counted in the background so that cached URLs are
+            // served without waiting for the DB. Errors are already recorded
+            // by the DB error metric.
+            tokio::spawn(async move {
+                match config.get_view_count_batching() {
+                    Some(batching) => {
+                        record_batched_view(&config, &short_id, batching).await;
+                    }
+                    None => {
+                        let _ =
+                            Url::increment_view_count_of(&config, &short_id)
+                                .await;
+                    }
+                }
+            });
+        }
+
+        metrics::record_url_hit();
+        metrics::record_url_cache_hit();
+        return Ok(Resolution::Found(full_url));
+    }
+
+    let batching = config.get_view_count_batching();
+    let url_object = if config.is_tracking_views() && batching.is_none() {
+        // The URL is fetched and its view counted in a single operation. It is
+        // fetched again only to tell why it does not resolve.
+        match Url::fetch_and_increment_view_count(config, short_id).await? {
+            Some(url_object) => url_object,
+            None => {
+                metrics::record_url_not_found();
+                return match Url::fetch_url(config, short_id).await? {
+                    Some(_) => Ok(Resolution::Gone),
+                    None => Ok(Resolution::NotFound),
+                };
+            }
+        }
+    } else {
+        let Some(mut url_object) = Url::fetch_url(config, short_id).await?
+        else {
+            metrics::record_url_not_found();
+            return Ok(Resolution::NotFound);
+        };
+
+        if url_object.is_expired() {
+            metrics::record_url_not_found();
+            return Ok(Resolution::Gone);
+        }
+
+        if let Some(max_views) = url_object.get_max_views() {
+            // The URL stops resolving once it was viewed `max_views` times.
+            if !url_object.increment_view_count_below(max_views).await? {
+                metrics::record_url_not_found();
+                return Ok(Resolution::Gone);
+            }
+        } else if let Some(batching) =
+            batching.filter(|_| config.is_tracking_views())
+        {
+            record_batched_view(config, short_id, batching).await;
+        }
+
+        url_object
     };
 
-    if url_object.is_none() {
-        return Ok(None);
+    // Only URLs that resolve the same way on every fetch can be cached.
+    if url_object.get_max_views().is_none() && !url_object.has_expiry() {
+        config
+            .get_cache()
+            .insert(short_id, url_object.get_full_url());
+    }
+
+    metrics::record_url_hit();
+
+    Ok(Resolution::Found(url_object.get_full_url().to_string()))
+}
+
+/// Adds a pending view of the provided short ID to the views batched by the
+/// provided configuration. The pending views are flushed right away if the
+/// batch is full, and otherwise by a background task once they are due.
+///
+/// A failed flush does not fail the fetch that triggered it, since the URL was
+/// fetched. The views that could not be written stay pending and the error is
+/// already recorded by the DB error metric.
+async fn record_batched_view(
+    config: &Config,
+    short_id: &str,
+    batching: ViewCountBatching,
+) {
+    let pending_views = config.get_pending_views();
+
+    if pending_views.record(short_id, batching) {
+        let _ = flush_pending_views(config).await;
+    }
+    if pending_views.start_flush_task() {
+        spawn_flush_task(config.clone(), batching.max_delay);
+    }
+}
+
+/// Spawns a task flushing the pending views of the provided configuration
+/// once `max_delay` elapsed since the oldest pending view. The task retries
+/// the views that could not be written and stops once no view is pending.
+fn spawn_flush_task(config: Config, max_delay: Duration) {
+    tokio::spawn(async move {
+        let pending_views = config.get_pending_views();
+        while let Some(delay) = pending_views.time_until_due(max_delay) {
+            if delay.is_zero() {
+                let _ = flush_pending_views(&config).await;
+            } else {
+                tokio::time::sleep(delay).await;
+            }
+        }
+    });
+}
+
+/// Writes the view counts batched by [`get_url`](get_url) to the DB in a
+/// single bulk update. Does nothing unless the view counts are batched with
+/// [`Config::batch_view_counts`](Config::batch_view_counts).
+///
+/// Call this function on graceful shutdown, so that the views that were not
+/// flushed yet are not lost when the process exits.
+///
+/// # Returns
+///
+/// The number of views that were flushed.
+///
+/// # Errors
+///
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer. The views that could not be written stay
+/// pending and are flushed with the next batch, the others are not written
+/// again.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{flush_view_counts, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// tokio::signal::ctrl_c().await.expect("could not listen for ctrl-c");
+/// let views = flush_view_counts().await?;
+/// println!("flushed {views} views");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub async fn flush_view_counts() -> Result<u64, ShortyError> {
+    flush_pending_views(&config::get()?).await
+}
+
+/// Writes the pending views of the provided configuration to its backend.
+///
+/// # Returns
+///
+/// The number of views that were flushed.
+async fn flush_pending_views(config: &Config) -> Result<u64, ShortyError> {
+    let pending_views = config.get_pending_views();
+    let counts = pending_views.take();
+    if counts.is_empty() {
+        return Ok(0);
+    }
+
+    match Url::increment_view_counts(config, &counts).await {
+        Ok(()) => Ok(counts.values().map(|&views| u64::from(views)).sum()),
+        // Only the views that were not written stay pending, the others
+        // would be counted twice by the next flush.
+        Err(failed) => {
+            pending_views.restore(failed.counts);
+            Err(failed.error)
+        }
+    }
+}
+
+/// Shuts the library down. The configuration set with [`init`](init) is
+/// removed along with its cached URLs and other state, then its pending
+/// batched view counts are flushed.
+///
+/// The MongoDB client of the configuration is dropped with its last clone,
+/// which closes its connection pool. Operations that were still running when
+/// the library was shut down, including the background tasks counting views,
+/// hold a clone until they complete. Views they batch after the flush are
+/// written by the background flush once they are due.
+///
+/// Every function of the library returns a
+/// [`NotInitialized`](ShortyError::NotInitialized) error after the shutdown,
+/// until the library is configured again with [`init`](init) or
+/// [`setup_db`](setup_db). Instances of [`MongoShortener`](MongoShortener)
+/// own their configuration and are not shut down.
+///
+/// # Errors
+///
+/// The function will return a [`NotInitialized`](ShortyError::NotInitialized)
+/// error if the library is not initialised, or a
+/// [`Database`](ShortyError::Database) error if the pending view counts could
+/// not be flushed. The configuration is set back if the flush fails, unless
+/// another one was set in the meantime, so that the shutdown can be retried.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{shutdown, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// tokio::signal::ctrl_c().await.expect("could not listen for ctrl-c");
+/// shutdown().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub async fn shutdown() -> Result<(), ShortyError> {
+    // The configuration is removed before the flush, so that no view can be
+    // recorded by a new operation once the pending views were taken.
+    let config = config::take()?;
+
+    if let Err(err) = flush_pending_views(&config).await {
+        config::restore(config);
+        return Err(err);
     }
 
-    let mut url_object = url_object.unwrap();
-    url_object.increment_view_count();
+    Ok(())
+}
+
+/// Information about a stored URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UrlInfo {
+    /// The short ID of the URL.
+    pub short_id: String,
+    /// The full URL stored against the short ID.
+    pub full_url: String,
+    /// Number of times the URL was fetched.
+    pub view_count: u32,
+    /// The labels attached to the URL with
+    /// [`CreateOptionsBuilder::metadata`](CreateOptionsBuilder::metadata).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
 
-    if let Err(e) = url_object.save().await {
-        println!("error while updating view count: {e}");
-        return Err("could not update URL view count");
+impl From<Url> for UrlInfo {
+    fn from(value: Url) -> Self {
+        UrlInfo {
+            short_id: value.get_short_id().to_string(),
+            full_url: value.get_full_url().to_string(),
+            view_count: value.get_view_count(),
+            metadata: value.get_metadata().clone(),
+        }
     }
+}
 
-    Ok(Some(url_object.get_full_url().to_string()))
+/// Counts the stored URLs.
+///
+/// # Errors
+///
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{count_urls, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// println!("{} URLs are stored", count_urls().await?);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn count_urls() -> Result<u64, ShortyError> {
+    Url::count(&config::get()?).await
+}
+
+/// Deletes every stored URL. Enable the `danger-purge` feature to use this
+/// function.
+///
+/// Meant for test environments and full resets. The cached URLs and the
+/// batched view counts are discarded as well.
+///
+/// # Returns
+///
+/// The number of deleted URLs.
+///
+/// # Errors
+///
+/// The function will return a [`Database`](ShortyError::Database) error if an
+/// error occurs at the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{purge_all, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let num_deleted = purge_all().await?;
+/// println!("deleted {num_deleted} URLs");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "danger-purge")]
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub async fn purge_all() -> Result<u64, ShortyError> {
+    let config = config::get()?;
+
+    let num_deleted = Url::delete_all(&config).await?;
+    config.get_cache().clear();
+    config.get_pending_views().take();
+
+    Ok(num_deleted)
+}
+
+/// Lists all the URLs labelled with the provided metadata key and value with
+/// [`CreateOptionsBuilder::metadata`](CreateOptionsBuilder::metadata). The
+/// lookup is backed by an index set up by [`setup_db`](setup_db).
+///
+/// # Errors
+///
+/// The function will return an
+/// [`InvalidMetadataKey`](ShortyError::InvalidMetadataKey) error if the key
+/// could not have been attached to a URL and a
+/// [`Database`](ShortyError::Database) error if an error occurs at the DB
+/// layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use shorty::{list_urls_by_tag, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// for url in list_urls_by_tag("campaign", "launch").await? {
+///     println!("{} -> {}", url.short_id, url.full_url);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn list_urls_by_tag(
+    key: &str,
+    value: &str,
+) -> Result<Vec<UrlInfo>, ShortyError> {
+    validate_metadata_key(key)?;
+
+    let urls = Url::fetch_by_metadata(&config::get()?, key, value).await?;
+
+    Ok(urls.into_iter().map(UrlInfo::from).collect())
+}
+
+/// Streams all the stored URLs.
+///
+/// The URLs are read from the DB incrementally as the stream is polled, so
+/// large collections can be processed without loading every URL into memory.
+///
+/// # Errors
+///
+/// The stream yields a [`Database`](ShortyError::Database) error if an error
+/// occurs at the DB layer.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use futures::TryStreamExt;
+/// # use shorty::{stream_urls, ShortyError};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let urls = stream_urls();
+/// futures::pin_mut!(urls);
+///
+/// while let Some(url) = urls.try_next().await? {
+///     println!("{} -> {}", url.short_id, url.full_url);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn stream_urls() -> impl Stream<Item = Result<UrlInfo, ShortyError>> {
+    stream::once(async { Url::stream_all(&config::get()?).await })
+        .try_flatten()
+        .map_ok(UrlInfo::from)
+        .map_err(ShortyError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, sync::Arc, time::Duration};
+
+    use tokio::sync::Mutex;
+
+    use super::*;
+    use crate::db::{urls::MemoryStore, Backend};
+
+    /// Serialises the tests replacing the global configuration.
+    static CONFIG_LOCK: Mutex<()> = Mutex::const_new(());
+
+    /// Gets the memory store of the provided configuration.
+    fn memory_store(config: &Config) -> Arc<MemoryStore> {
+        match config.get_backend() {
+            Backend::Memory(store) => store.clone(),
+            Backend::MongoDb { .. } => panic!("config is not in memory"),
+        }
+    }
+
+    /// Fetching a URL and counting its view must be a single DB operation.
+    #[tokio::test]
+    async fn get_url_performs_single_operation() {
+        let _guard = CONFIG_LOCK.lock().await;
+
+        let config = Config::in_memory();
+        let store = memory_store(&config);
+        init(config);
+
+        const URL: &str = "https://example.com/single-operation";
+        let short_id = create_url(URL).await.unwrap();
+
+        assert_eq!(get_url(&short_id).await.unwrap().as_deref(), Some(URL));
+        assert_eq!(store.operation_count("find_and_increment_view_count"), 1);
+        assert_eq!(store.operation_count("find_by_short_id"), 0);
+        assert_eq!(store.operation_count("update"), 0);
+    }
+
+    /// Only the batched views that failed to be written must stay pending, so
+    /// that the others are not written twice.
+    #[tokio::test]
+    async fn only_failed_views_restored() {
+        let _guard = CONFIG_LOCK.lock().await;
+
+        let store =
+            Arc::new(MemoryStore::failing_increments("failing", || {
+                mongodb::error::Error::from(io::ErrorKind::ConnectionReset)
+                    .into()
+            }));
+        let config = Config::with_memory_store(store)
+            .batch_view_counts(100, Duration::from_secs(60 * 60));
+        init(config.clone());
+
+        for short_id in ["failing", "written"] {
+            create_url_with_id("https://example.com/flushed", short_id)
+                .await
+                .unwrap();
+            get_url(short_id).await.unwrap();
+        }
+
+        let flushed = flush_view_counts().await;
+        assert!(matches!(flushed, Err(ShortyError::Database(_))));
+
+        let view_counts: BTreeMap<_, _> = stream_urls()
+            .map_ok(|url| (url.short_id, url.view_count))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(view_counts["written"], 1);
+        assert_eq!(view_counts["failing"], 0);
+
+        let pending = config.get_pending_views().take();
+        assert_eq!(pending, BTreeMap::from([("failing".to_string(), 1)]));
+    }
+
+    /// A failed flush of the batched views must not fail the fetch that
+    /// triggered it.
+    #[tokio::test]
+    async fn failed_flush_still_returns_url() {
+        let _guard = CONFIG_LOCK.lock().await;
+
+        let store =
+            Arc::new(MemoryStore::failing_increments("failing", || {
+                mongodb::error::Error::from(io::ErrorKind::ConnectionReset)
+                    .into()
+            }));
+        let config = Config::with_memory_store(store)
+            .batch_view_counts(1, Duration::from_secs(60 * 60));
+        init(config.clone());
+
+        const URL: &str = "https://example.com/flush-failed";
+        create_url_with_id(URL, "failing").await.unwrap();
+
+        assert_eq!(get_url("failing").await.unwrap().as_deref(), Some(URL));
+        let pending = config.get_pending_views().take();
+        assert_eq!(pending, BTreeMap::from([("failing".to_string(), 1)]));
+    }
+
+    /// A shutdown whose flush fails must keep the library configured, so that
+    /// it can be retried.
+    #[tokio::test]
+    async fn failed_shutdown_keeps_config() {
+        let _guard = CONFIG_LOCK.lock().await;
+
+        let store =
+            Arc::new(MemoryStore::failing_increments("failing", || {
+                mongodb::error::Error::from(io::ErrorKind::ConnectionReset)
+                    .into()
+            }));
+        init(
+            Config::with_memory_store(store)
+                .batch_view_counts(100, Duration::from_secs(60 * 60)),
+        );
+
+        create_url_with_id("https://example.com/shutdown", "failing")
+            .await
+            .unwrap();
+        get_url("failing").await.unwrap();
+
+        assert!(matches!(shutdown().await, Err(ShortyError::Database(_))));
+        assert!(config::is_set());
+    }
+
+    /// A cached URL must be served without fetching it from the DB again.
+    #[cfg(feature = "cache")]
+    #[tokio::test]
+    async fn cached_url_not_fetched_again() {
+        let _guard = CONFIG_LOCK.lock().await;
+
+        let config = Config::in_memory().cache_capacity(16);
+        let store = memory_store(&config);
+        init(config);
+
+        const URL: &str = "https://example.com/cached";
+        let short_id = create_url(URL).await.unwrap();
+
+        assert_eq!(get_url(&short_id).await.unwrap().as_deref(), Some(URL));
+        assert_eq!(get_url(&short_id).await.unwrap().as_deref(), Some(URL));
+        assert_eq!(store.operation_count("find_and_increment_view_count"), 1);
+
+        assert!(delete_url(&short_id).await.unwrap());
+        assert_eq!(get_url(&short_id).await.unwrap(), None);
+    }
 }
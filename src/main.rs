@@ -7,7 +7,8 @@
 //!
 //! # Usage
 //!
-//! The tool provides 2 commands "shorten" and "lengthen".
+//! The tool provides 3 commands "shorten", "lengthen" and "export", and the
+//! "--help" and "--version" flags.
 //!
 //! ## Shortening URLs
 //!
@@ -43,20 +44,64 @@
 //!
 //! After successfully lengthening the URL, the full UR will be printed as the
 //! output. If the provided short ID was not found, "not found" will be printed
-//! as the output to STDERR.
+//! as the output to STDERR and the tool exits with the code 2.
+//!
+//! ## Exporting URLs
+//!
+//! Run the following command to print all the stored URLs as line-delimited
+//! JSON:
+//!
+//! ```bash
+//! $ cargo run -- export
+//! ```
+//!
+//! ## Help and version
+//!
+//! Pass `-h` or `--help` to print the usage information and `-V` or
+//! `--version` to print the version of the tool:
+//!
+//! ```bash
+//! $ cargo run -- --help
+//! $ cargo run -- --version
+//! ```
+//!
+//! ## Exit codes
+//!
+//! The tool exits with one of the following codes:
+//!
+//! - `0` if the command succeeded.
+//! - `1` if the arguments of the command or the URL to shorten are invalid.
+//! - `2` if the short ID to lengthen was not found.
+//! - `3` if the database could not be configured or reached, no unused short
+//!   ID could be generated, or the output could not be written.
 
 mod cli_utils;
 
-use std::{env, process};
+use std::{
+    env,
+    io::{self, Write},
+};
+
+use futures::TryStreamExt;
+
+use crate::cli_utils::{ExitStatus, ShortyArgs, ShortyCommand, USAGE};
 
-use crate::cli_utils::{ShortyArgs, ShortyCommand};
+/// Sets up the DB before running a command that requires it.
+async fn setup_db() {
+    if let Err(e) = shorty::setup_db().await {
+        eprintln!("{e}");
+        ExitStatus::of(&e).exit();
+    }
+}
 
 async fn handle_shorten_url(full_url: String) {
+    setup_db().await;
+
     let short_id = match shorty::create_url(&full_url).await {
         Ok(id) => id,
         Err(err) => {
             eprintln!("{err}");
-            process::exit(1);
+            ExitStatus::of(&err).exit();
         }
     };
 
@@ -64,35 +109,51 @@ async fn handle_shorten_url(full_url: String) {
 }
 
 async fn handle_lengthen_short_id(short_id: String) {
-    let full_url = match shorty::get_url(&short_id).await {
-        Ok(url) => url,
-        Err(err) => {
-            eprintln!("{err}");
-            process::exit(1);
-        }
-    };
+    setup_db().await;
 
-    if full_url.is_none() {
-        eprintln!("not found");
-        process::exit(1);
+    match ExitStatus::of_lengthened(shorty::get_url(&short_id).await) {
+        Ok(full_url) => println!("{full_url}"),
+        Err((status, message)) => {
+            eprintln!("{message}");
+            status.exit();
+        }
     }
+}
+
+async fn handle_export() {
+    setup_db().await;
+
+    let urls = shorty::stream_urls();
+    futures::pin_mut!(urls);
+
+    let mut stdout = io::stdout().lock();
 
-    let full_url = full_url.unwrap();
-    println!("{full_url}");
+    loop {
+        let url = match urls.try_next().await {
+            Ok(Some(url)) => url,
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("{err}");
+                ExitStatus::of(&err).exit();
+            }
+        };
+
+        let line =
+            serde_json::to_string(&url).expect("could not serialise URL");
+        if let Err(err) = writeln!(stdout, "{line}") {
+            eprintln!("{err}");
+            ExitStatus::Backend.exit();
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = shorty::setup_db().await {
-        eprintln!("{e}");
-        process::exit(1);
-    }
-
     let args = match ShortyArgs::build(env::args()) {
         Ok(arg) => arg,
         Err(err_string) => {
             eprintln!("{err_string}");
-            process::exit(1);
+            ExitStatus::Usage.exit();
         }
     };
 
@@ -101,5 +162,10 @@ async fn main() {
         ShortyCommand::Lengthen(short_id) => {
             handle_lengthen_short_id(short_id).await
         }
+        ShortyCommand::Export => handle_export().await,
+        ShortyCommand::Help => println!("{USAGE}"),
+        ShortyCommand::Version => {
+            println!("shorty {}", env!("CARGO_PKG_VERSION"))
+        }
     };
 }
@@ -0,0 +1,60 @@
+//! Counters recorded through the [`metrics`](https://docs.rs/metrics) facade
+//! when the `metrics` feature is enabled. Without the feature, the functions
+//! in this module do nothing.
+//!
+//! The following counters are recorded:
+//!
+//! | Name                          | Description                              |
+//! |-------------------------------|------------------------------------------|
+//! | `shorty_urls_created_total`   | Number of short IDs created.             |
+//! | `shorty_url_hits_total`       | Number of short IDs found by `get_url`.  |
+//! | `shorty_url_cache_hits_total` | Number of hits served from the cache.    |
+//! | `shorty_url_not_found_total`  | Number of short IDs not found.           |
+//! | `shorty_db_errors_total`      | Number of errors returned by MongoDB.    |
+
+/// Name of the counter for the created short IDs.
+pub const URLS_CREATED: &str = "shorty_urls_created_total";
+/// Name of the counter for the short IDs that were found.
+pub const URL_HITS: &str = "shorty_url_hits_total";
+/// Name of the counter for the short IDs that were found in the cache.
+pub const URL_CACHE_HITS: &str = "shorty_url_cache_hits_total";
+/// Name of the counter for the short IDs that were not found.
+pub const URL_NOT_FOUND: &str = "shorty_url_not_found_total";
+/// Name of the counter for the errors returned by MongoDB.
+pub const DB_ERRORS: &str = "shorty_db_errors_total";
+
+/// Increments the counter with the provided name.
+#[cfg(feature = "metrics")]
+fn increment(name: &'static str) {
+    ::metrics::counter!(name).increment(1);
+}
+
+/// Increments the counter with the provided name.
+#[cfg(not(feature = "metrics"))]
+fn increment(_name: &'static str) {}
+
+/// Records that a new short ID was created.
+pub(crate) fn record_url_created() {
+    increment(URLS_CREATED);
+}
+
+/// Records that a short ID was found.
+pub(crate) fn record_url_hit() {
+    increment(URL_HITS);
+}
+
+/// Records that a short ID was found in the cache. The hit itself is recorded
+/// with [`record_url_hit`].
+pub(crate) fn record_url_cache_hit() {
+    increment(URL_CACHE_HITS);
+}
+
+/// Records that a short ID was not found.
+pub(crate) fn record_url_not_found() {
+    increment(URL_NOT_FOUND);
+}
+
+/// Records that MongoDB returned an error.
+pub(crate) fn record_db_error() {
+    increment(DB_ERRORS);
+}
@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::id::DEFAULT_ID_LENGTH;
+
+/// Options for shortening a URL with
+/// [`create_url_with_options`](crate::create_url_with_options).
+///
+/// The default options generate a short ID of 10 characters for the full URL
+/// as provided, like [`create_url`](crate::create_url).
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use shorty::CreateOptions;
+/// #
+/// let options = CreateOptions::new()
+///     .length(8)
+///     .expiry(Duration::from_secs(24 * 60 * 60))
+///     .max_views(100)
+///     .metadata("campaign", "launch")
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CreateOptions {
+    /// Number of characters generated for the short ID, excluding the prefix.
+    length: usize,
+    /// The prefix of the generated short ID.
+    prefix: Option<String>,
+    /// The short ID used instead of a generated one.
+    vanity: Option<String>,
+    /// The duration after which the URL stops resolving.
+    expiry: Option<Duration>,
+    /// Number of times the URL can be fetched before it stops resolving.
+    max_views: Option<u32>,
+    /// Set if the existing short ID of an already shortened URL must be
+    /// returned instead of creating a new one.
+    deduplicate: bool,
+    /// Set if the full URL must be normalised before it is stored.
+    normalize: bool,
+    /// Arbitrary labels attached to the URL.
+    metadata: BTreeMap<String, String>,
+}
+
+impl CreateOptions {
+    /// Creates a new [`CreateOptionsBuilder`] starting from the default
+    /// options.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> CreateOptionsBuilder {
+        CreateOptionsBuilder::default()
+    }
+
+    /// Creates a new [`CreateOptionsBuilder`] starting from the default
+    /// options, same as [`new`](Self::new).
+    pub fn builder() -> CreateOptionsBuilder {
+        CreateOptionsBuilder::default()
+    }
+
+    /// Gets the number of characters generated for the short ID.
+    pub(crate) fn get_length(&self) -> usize {
+        self.length
+    }
+
+    /// Gets the prefix of the generated short ID, if any.
+    pub(crate) fn get_prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// Gets the vanity short ID, if any.
+    pub(crate) fn get_vanity(&self) -> Option<&str> {
+        self.vanity.as_deref()
+    }
+
+    /// Gets the duration after which the URL stops resolving, if any.
+    pub(crate) fn get_expiry(&self) -> Option<Duration> {
+        self.expiry
+    }
+
+    /// Gets the maximum number of views of the URL, if any.
+    pub(crate) fn get_max_views(&self) -> Option<u32> {
+        self.max_views
+    }
+
+    /// Checks if already shortened URLs must be deduplicated.
+    pub(crate) fn is_deduplicating(&self) -> bool {
+        self.deduplicate
+    }
+
+    /// Checks if the full URL must be normalised.
+    pub(crate) fn is_normalizing(&self) -> bool {
+        self.normalize
+    }
+
+    /// Gets the labels attached to the URL.
+    pub(crate) fn get_metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    /// Gets the name of the first set option that cannot be combined with
+    /// deduplication, if the URL is deduplicated.
+    pub(crate) fn get_conflicting_option(&self) -> Option<&'static str> {
+        if !self.deduplicate {
+            return None;
+        }
+
+        [
+            ("vanity", self.vanity.is_some()),
+            ("prefix", self.prefix.is_some()),
+            ("expiry", self.expiry.is_some()),
+            ("max_views", self.max_views.is_some()),
+            ("metadata", !self.metadata.is_empty()),
+        ]
+        .into_iter()
+        .find_map(|(option, is_set)| is_set.then_some(option))
+    }
+}
+
+impl Default for CreateOptions {
+    fn default() -> Self {
+        CreateOptions {
+            length: DEFAULT_ID_LENGTH,
+            prefix: None,
+            vanity: None,
+            expiry: None,
+            max_views: None,
+            deduplicate: false,
+            normalize: false,
+            metadata: BTreeMap::new(),
+        }
+    }
+}
+
+/// Builds [`CreateOptions`]. Created with
+/// [`CreateOptions::builder`](CreateOptions::builder).
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptionsBuilder {
+    /// The options being built.
+    options: CreateOptions,
+}
+
+impl CreateOptionsBuilder {
+    /// Sets the number of characters generated for the short ID, excluding
+    /// the prefix. Defaults to 10. Ignored if a vanity short ID is set.
+    pub fn length(mut self, length: usize) -> Self {
+        self.options.length = length;
+        self
+    }
+
+    /// Sets the prefix of the generated short ID, see
+    /// [`create_url_with_prefix`](crate::create_url_with_prefix). Ignored if a
+    /// vanity short ID is set.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.options.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the short ID used instead of a generated one, see
+    /// [`create_url_with_id`](crate::create_url_with_id).
+    pub fn vanity(mut self, short_id: impl Into<String>) -> Self {
+        self.options.vanity = Some(short_id.into());
+        self
+    }
+
+    /// Sets the duration after which [`get_url`](crate::get_url) stops
+    /// resolving the URL.
+    pub fn expiry(mut self, expiry: Duration) -> Self {
+        self.options.expiry = Some(expiry);
+        self
+    }
+
+    /// Sets the number of times the URL can be fetched before it stops
+    /// resolving, see
+    /// [`create_url_with_max_views`](crate::create_url_with_max_views).
+    pub fn max_views(mut self, max_views: u32) -> Self {
+        self.options.max_views = Some(max_views);
+        self
+    }
+
+    /// Sets whether the existing short ID is returned if the full URL was
+    /// already shortened, see
+    /// [`create_url_deduplicated`](crate::create_url_deduplicated). Defaults
+    /// to `false`.
+    ///
+    /// The reused URL may have been created with other options, so
+    /// deduplication can only be combined with [`length`](Self::length) and
+    /// [`normalize`](Self::normalize), and the length only applies to a newly
+    /// created short ID. Creating a URL fails with a
+    /// [`ConflictingOptions`](crate::ShortyError::ConflictingOptions) error if
+    /// a vanity short ID, a prefix, an expiry, a maximum number of views or
+    /// metadata is set as well.
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.options.deduplicate = deduplicate;
+        self
+    }
+
+    /// Sets whether the full URL is normalised with
+    /// [`normalize_url`](crate::normalize_url) before it is stored. Defaults to
+    /// `false`.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.options.normalize = normalize;
+        self
+    }
+
+    /// Attaches the label `key` with the provided value to the URL, replacing
+    /// any value set earlier for the same key. The labels are returned by
+    /// [`stream_urls`](crate::stream_urls) and the URLs can be filtered by them
+    /// with [`list_urls_by_tag`](crate::list_urls_by_tag).
+    ///
+    /// Creating the URL fails with an
+    /// [`InvalidMetadataKey`](crate::ShortyError::InvalidMetadataKey) error if
+    /// the key is empty, contains a `.` or starts with a `$`.
+    pub fn metadata(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.options.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Builds the options.
+    pub fn build(self) -> CreateOptions {
+        self.options
+    }
+}
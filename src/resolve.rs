@@ -0,0 +1,104 @@
+//! Resolution of the redirects of a URL before it is shortened. Enable the
+//! `resolve` feature to use this module.
+
+use std::{collections::HashSet, error::Error, fmt};
+
+use reqwest::{header, redirect::Policy, Client};
+use url::Url;
+
+use crate::{config::ResolveTimeout, url_utils::validate_url, ShortyError};
+
+/// Reasons the redirects of a URL could not be resolved.
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The request to one of the URLs in the redirect chain failed. The
+    /// variant stores the error returned by the HTTP client.
+    Request(reqwest::Error),
+    /// The redirect chain visits the same URL more than once.
+    Loop,
+    /// The redirect chain has more hops than the configured maximum.
+    TooManyHops,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(err) => write!(f, "request failed: {err}"),
+            Self::Loop => write!(f, "redirect loop detected"),
+            Self::TooManyHops => write!(f, "too many redirects"),
+        }
+    }
+}
+
+impl Error for ResolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Request(err) => Some(err),
+            Self::Loop | Self::TooManyHops => None,
+        }
+    }
+}
+
+impl From<ResolveError> for ShortyError {
+    fn from(value: ResolveError) -> Self {
+        Self::Resolve(value)
+    }
+}
+
+/// Follows the redirects of the provided URL and returns the final
+/// destination. The URL is returned unchanged if it does not redirect.
+///
+/// # Errors
+///
+/// Returns an [`InvalidUrl`](ShortyError::InvalidUrl) error if a URL in the
+/// redirect chain is not a valid HTTP or HTTPS URL or a
+/// [`Resolve`](ShortyError::Resolve) error if the redirects could not be
+/// followed within `max_hops` redirects or a request timed out.
+pub(crate) async fn resolve_redirects(
+    full_url: &str,
+    max_hops: usize,
+    timeout: ResolveTimeout,
+) -> Result<String, ShortyError> {
+    let client = Client::builder()
+        .redirect(Policy::none())
+        .connect_timeout(timeout.connect)
+        .timeout(timeout.request)
+        .build()
+        .map_err(ResolveError::Request)?;
+
+    let mut current_url = full_url.to_string();
+    let mut visited_urls = HashSet::from([current_url.clone()]);
+
+    // One more request than the number of hops is needed to find out that the
+    // last URL does not redirect.
+    for hops in 0..=max_hops {
+        let response = client
+            .get(&current_url)
+            .send()
+            .await
+            .map_err(ResolveError::Request)?;
+
+        let location = match response.headers().get(header::LOCATION) {
+            Some(location) if response.status().is_redirection() => location,
+            _ => return Ok(current_url),
+        };
+        if hops == max_hops {
+            return Err(ResolveError::TooManyHops.into());
+        }
+
+        let location =
+            location.to_str().map_err(|_| ShortyError::InvalidUrl)?;
+        let next_url = Url::parse(&current_url)
+            .and_then(|base| base.join(location))
+            .map_err(|_| ShortyError::InvalidUrl)?
+            .to_string();
+        validate_url(&next_url)?;
+
+        if !visited_urls.insert(next_url.clone()) {
+            return Err(ResolveError::Loop.into());
+        }
+        current_url = next_url;
+    }
+
+    unreachable!("the last hop either returns or errors")
+}
@@ -0,0 +1,112 @@
+//! An [`axum`](https://docs.rs/axum) router redirecting short IDs to their
+//! full URLs. Enable the `server` feature to use this module.
+//!
+//! Flush the batched view counts with
+//! [`flush_view_counts`](crate::flush_view_counts) once the server shut down.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use shorty::server::{router, RedirectStatus};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     shorty::setup_db().await.expect("could not setup DB");
+//!
+//!     let app = router(RedirectStatus::Temporary);
+//!     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
+//!         .await
+//!         .unwrap();
+//!     axum::serve(listener, app)
+//!         .with_graceful_shutdown(async {
+//!             tokio::signal::ctrl_c().await.unwrap();
+//!         })
+//!         .await
+//!         .unwrap();
+//!
+//!     shorty::flush_view_counts()
+//!         .await
+//!         .expect("could not flush view counts");
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::{encode_location, resolve, Resolution};
+
+/// The status code of the redirect responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectStatus {
+    /// Redirect with a `301 Moved Permanently`. Browsers cache the redirect,
+    /// so later visits may not reach the server.
+    Permanent,
+    /// Redirect with a `302 Found`. Browsers do not cache the redirect.
+    Temporary,
+}
+
+impl From<RedirectStatus> for StatusCode {
+    fn from(value: RedirectStatus) -> Self {
+        match value {
+            RedirectStatus::Permanent => StatusCode::MOVED_PERMANENTLY,
+            RedirectStatus::Temporary => StatusCode::FOUND,
+        }
+    }
+}
+
+/// Decides the status code of the redirect for a short ID.
+///
+/// Implement this trait to pick the status code per short ID, e.g. to redirect
+/// campaign links temporarily and everything else permanently.
+pub trait RedirectPolicy: Send + Sync + 'static {
+    /// Gets the status code of the redirect from the short ID to the full URL.
+    fn redirect_status(&self, short_id: &str, full_url: &str)
+        -> RedirectStatus;
+}
+
+impl RedirectPolicy for RedirectStatus {
+    fn redirect_status(&self, _short_id: &str, _full_url: &str) -> Self {
+        *self
+    }
+}
+
+/// Creates a router that redirects `GET /<short_id>` to the full URL stored
+/// against the short ID with the status code chosen by the `policy`. The full
+/// URL is encoded with [`encode_location`](crate::encode_location).
+///
+/// Responds with a `404 Not Found` if the short ID does not exist, with a
+/// `410 Gone` if its URL expired or reached its maximum number of views and
+/// with a `500 Internal Server Error` if the full URL could not be fetched.
+pub fn router(policy: impl RedirectPolicy) -> Router {
+    let policy: Arc<dyn RedirectPolicy> = Arc::new(policy);
+
+    Router::new()
+        .route("/:short_id", get(redirect))
+        .with_state(policy)
+}
+
+/// Redirects the short ID in the path to its full URL.
+async fn redirect(
+    State(policy): State<Arc<dyn RedirectPolicy>>,
+    Path(short_id): Path<String>,
+) -> Response {
+    let full_url = match resolve(&short_id).await {
+        Ok(Resolution::Found(url)) => url,
+        Ok(Resolution::Gone) => return StatusCode::GONE.into_response(),
+        Ok(Resolution::NotFound) => {
+            return StatusCode::NOT_FOUND.into_response()
+        }
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let status = policy.redirect_status(&short_id, &full_url);
+    let location = encode_location(&full_url);
+    (StatusCode::from(status), [(header::LOCATION, location)]).into_response()
+}
@@ -0,0 +1,134 @@
+//! A [`Shortener`] trait over the operations of the library, so that
+//! applications can hold an `Arc<dyn Shortener>` in their state and swap in a
+//! fake implementation in their tests.
+
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::{
+    config::Config, create_with_options, db::urls::Url, delete_short_id,
+    fetch_full_url, flush_pending_views, CreateOptions, SetupReport,
+    ShortyError,
+};
+
+/// Creates, fetches and deletes shortened URLs.
+///
+/// The trait is object safe, so that it can be used as `Arc<dyn Shortener>`.
+/// [`MongoShortener`] implements it with the same behaviour as the free
+/// functions of the library.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+///
+/// use shorty::{Config, MongoShortener, Shortener, ShortyError};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), ShortyError> {
+/// let shortener: Arc<dyn Shortener> = Arc::new(MongoShortener::new(
+///     Config::new("mongodb://localhost:27017", "shorty"),
+/// ));
+///
+/// let short_id = shortener.create("https://example.com").await?;
+/// assert!(shortener.get(&short_id).await?.is_some());
+/// # Ok(())
+/// # }
+/// ```
+pub trait Shortener: Send + Sync {
+    /// Creates a shortened URL for the provided full URL, see
+    /// [`create_url`](crate::create_url).
+    ///
+    /// # Returns
+    ///
+    /// The generated short ID for the full URL.
+    fn create<'a>(
+        &'a self,
+        full_url: &'a str,
+    ) -> BoxFuture<'a, Result<String, ShortyError>>;
+
+    /// Gets the full URL stored against the provided short ID, see
+    /// [`get_url`](crate::get_url).
+    fn get<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, ShortyError>>;
+
+    /// Deletes the URL stored against the provided short ID, see
+    /// [`delete_url`](crate::delete_url).
+    ///
+    /// # Returns
+    ///
+    /// Whether a URL was deleted.
+    fn delete<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ShortyError>>;
+}
+
+/// A [`Shortener`] storing the URLs in the backend of its own [`Config`],
+/// independently of the configuration set with [`init`](crate::init).
+///
+/// Every clone of a `MongoShortener` shares the same cached URLs and pending
+/// view counts.
+#[derive(Debug, Clone)]
+pub struct MongoShortener {
+    /// The configuration of the shortener.
+    config: Config,
+}
+
+impl MongoShortener {
+    /// Creates a new `MongoShortener` with the provided configuration.
+    pub fn new(config: Config) -> Self {
+        MongoShortener { config }
+    }
+
+    /// Sets up the backend of the shortener, see
+    /// [`setup_db`](crate::setup_db).
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`setup_db`](crate::setup_db), except for
+    /// [`MissingEnv`](ShortyError::MissingEnv).
+    pub async fn setup(&self) -> Result<SetupReport, ShortyError> {
+        Url::setup(&self.config).await
+    }
+
+    /// Writes the view counts batched by the shortener to its backend, see
+    /// [`flush_view_counts`](crate::flush_view_counts).
+    ///
+    /// # Returns
+    ///
+    /// The number of views that were flushed.
+    pub async fn flush_view_counts(&self) -> Result<u64, ShortyError> {
+        flush_pending_views(&self.config).await
+    }
+}
+
+impl Shortener for MongoShortener {
+    fn create<'a>(
+        &'a self,
+        full_url: &'a str,
+    ) -> BoxFuture<'a, Result<String, ShortyError>> {
+        async move {
+            let options = CreateOptions::default();
+            let outcome =
+                create_with_options(&self.config, full_url, options).await?;
+            Ok(outcome.short_id)
+        }
+        .boxed()
+    }
+
+    fn get<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, ShortyError>> {
+        fetch_full_url(&self.config, short_id).boxed()
+    }
+
+    fn delete<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ShortyError>> {
+        delete_short_id(&self.config, short_id).boxed()
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers for the `tracing` instrumentation enabled by the `tracing` feature.
+//! Without the feature, the functions in this module do nothing.
+
+/// Records the provided short ID on the current span.
+#[cfg(feature = "tracing")]
+pub(crate) fn record_short_id(short_id: &str) {
+    tracing::Span::current().record("short_id", short_id);
+}
+
+/// Records the provided short ID on the current span.
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record_short_id(_short_id: &str) {}
@@ -0,0 +1,246 @@
+use url::{Host, ParseError, Url};
+
+use crate::ShortyError;
+
+/// The schemes of the URLs that can be shortened.
+const ALLOWED_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Parses the provided URL and checks that it is an HTTP or HTTPS URL with a
+/// host. Internationalised hosts are accepted both in their Unicode and
+/// punycode forms.
+fn parse_url(full_url: &str) -> Result<Url, ShortyError> {
+    let parsed = Url::parse(full_url).map_err(|_| ShortyError::InvalidUrl)?;
+
+    if !ALLOWED_SCHEMES.contains(&parsed.scheme()) || !parsed.has_host() {
+        return Err(ShortyError::InvalidUrl);
+    }
+
+    Ok(parsed)
+}
+
+/// Checks if the provided URL can be shortened.
+///
+/// # Errors
+///
+/// Returns an [`InvalidUrl`](ShortyError::InvalidUrl) error if the URL is not
+/// a valid HTTP or HTTPS URL.
+pub(crate) fn validate_url(full_url: &str) -> Result<(), ShortyError> {
+    parse_url(full_url).map(|_| ())
+}
+
+/// Prepends `scheme://` to the provided URL if it is a bare host, optionally
+/// followed by a path, query or fragment, e.g. `example.com/sale`. A bare host
+/// is a domain with at least one `.` or an IP address, without credentials.
+///
+/// # Returns
+///
+/// The URL with the scheme if it was a bare host, otherwise the URL as
+/// provided.
+pub(crate) fn with_default_scheme(full_url: &str, scheme: &str) -> String {
+    // Only URLs without any scheme are coerced.
+    if !matches!(
+        Url::parse(full_url),
+        Err(ParseError::RelativeUrlWithoutBase)
+    ) || !full_url.starts_with(|ch: char| ch.is_alphanumeric() || ch == '[')
+    {
+        return full_url.to_string();
+    }
+
+    let coerced = format!("{scheme}://{full_url}");
+    let is_bare_host = parse_url(&coerced).is_ok_and(|parsed| {
+        let is_host = match parsed.host() {
+            Some(Host::Domain(domain)) => domain.contains('.'),
+            Some(Host::Ipv4(_) | Host::Ipv6(_)) => true,
+            None => false,
+        };
+        is_host && parsed.username().is_empty() && parsed.password().is_none()
+    });
+
+    if is_bare_host {
+        coerced
+    } else {
+        full_url.to_string()
+    }
+}
+
+/// Checks if the provided URL points at the host of the provided base URL.
+/// Hosts are compared in their normalised form, so differences in case or
+/// between the Unicode and punycode forms do not matter. A base URL without
+/// a scheme, e.g. `sho.rt`, is parsed as an HTTPS URL. Any other base URL
+/// that is not a valid HTTP or HTTPS URL matches no URL.
+pub(crate) fn has_same_host(full_url: &str, base_url: &str) -> bool {
+    let base_url = match Url::parse(base_url) {
+        Err(ParseError::RelativeUrlWithoutBase) => {
+            parse_url(&format!("https://{base_url}"))
+        }
+        _ => parse_url(base_url),
+    };
+
+    match (parse_url(full_url), base_url) {
+        (Ok(full_url), Ok(base_url)) => full_url.host() == base_url.host(),
+        _ => false,
+    }
+}
+
+/// Normalises the provided URL to its canonical form.
+///
+/// Internationalised hosts are converted to their punycode form, so the
+/// Unicode and punycode forms of the same URL normalise to the same value.
+///
+/// # Errors
+///
+/// Returns an [`InvalidUrl`](ShortyError::InvalidUrl) error if the URL is not
+/// a valid HTTP or HTTPS URL.
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::normalize_url;
+/// #
+/// let normalized = normalize_url("https://münchen.de").unwrap();
+/// assert_eq!(normalized, "https://xn--mnchen-3ya.de/");
+/// ```
+pub fn normalize_url(full_url: &str) -> Result<String, ShortyError> {
+    parse_url(full_url).map(String::from)
+}
+
+/// Encodes the provided full URL for use in the `Location` header of a
+/// redirect.
+///
+/// Characters that are not allowed in a URL, like spaces and raw Unicode, are
+/// percent-encoded and internationalised hosts are converted to their
+/// punycode form. Existing percent-encoded sequences are kept as is, so
+/// already encoded URLs are not encoded twice.
+///
+/// # Examples
+///
+/// ```rust
+/// # use shorty::encode_location;
+/// #
+/// let location = encode_location("https://example.com/summer sale");
+/// assert_eq!(location, "https://example.com/summer%20sale");
+/// ```
+pub fn encode_location(full_url: &str) -> String {
+    match parse_url(full_url) {
+        Ok(parsed) => parsed.into(),
+        // The stored URLs are always valid, but any other value is still
+        // encoded so that it can be used in a header.
+        Err(_) => full_url
+            .bytes()
+            .map(|byte| match byte {
+                b'!'..=b'~' => char::from(byte).to_string(),
+                _ => format!("%{byte:02X}"),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNICODE_URL: &str = "https://münchen.de";
+    const PUNYCODE_URL: &str = "https://xn--mnchen-3ya.de";
+
+    #[test]
+    fn should_accept_unicode_host() {
+        assert!(validate_url(UNICODE_URL).is_ok());
+    }
+
+    #[test]
+    fn should_accept_punycode_host() {
+        assert!(validate_url(PUNYCODE_URL).is_ok());
+    }
+
+    #[test]
+    fn should_normalize_idn_forms_to_same_value() {
+        let unicode = normalize_url(UNICODE_URL).unwrap();
+        let punycode = normalize_url(PUNYCODE_URL).unwrap();
+
+        assert_eq!(unicode, punycode);
+        assert_eq!(unicode, "https://xn--mnchen-3ya.de/");
+    }
+
+    #[test]
+    fn should_not_accept_other_schemes() {
+        assert!(matches!(
+            validate_url("ftp://example.com"),
+            Err(ShortyError::InvalidUrl)
+        ));
+    }
+
+    #[test]
+    fn should_not_accept_invalid_url() {
+        assert!(matches!(
+            validate_url("https;//example.com"),
+            Err(ShortyError::InvalidUrl)
+        ));
+    }
+
+    #[test]
+    fn should_add_default_scheme_to_bare_host() {
+        for (full_url, expected) in [
+            ("example.com", "https://example.com"),
+            ("example.com/sale?q=1", "https://example.com/sale?q=1"),
+            ("127.0.0.1/path", "https://127.0.0.1/path"),
+        ] {
+            assert_eq!(with_default_scheme(full_url, "https"), expected);
+        }
+    }
+
+    #[test]
+    fn should_not_add_default_scheme_to_other_urls() {
+        for full_url in [
+            "http://example.com",
+            "mailto:user@example.com",
+            "user@example.com",
+            "/relative/path",
+            "not a url",
+            "localhost",
+        ] {
+            assert_eq!(with_default_scheme(full_url, "https"), full_url);
+        }
+    }
+
+    #[test]
+    fn should_match_same_host() {
+        assert!(has_same_host("https://SHO.rt/xyz", "https://sho.rt"));
+        assert!(has_same_host(PUNYCODE_URL, "http://münchen.de/"));
+    }
+
+    #[test]
+    fn should_match_base_url_without_scheme() {
+        assert!(has_same_host("https://sho.rt/xyz", "sho.rt"));
+        assert!(has_same_host("http://SHO.RT/xyz", "sho.rt/"));
+        assert!(!has_same_host("https://example.com/xyz", "sho.rt"));
+    }
+
+    #[test]
+    fn should_not_match_other_host() {
+        assert!(!has_same_host("https://example.com/xyz", "https://sho.rt"));
+        assert!(!has_same_host("https://sub.sho.rt/xyz", "https://sho.rt"));
+    }
+
+    #[test]
+    fn should_encode_space_in_location() {
+        assert_eq!(
+            encode_location("https://example.com/summer sale?q=a b"),
+            "https://example.com/summer%20sale?q=a%20b"
+        );
+    }
+
+    #[test]
+    fn should_not_encode_location_twice() {
+        const ENCODED_URL: &str = "https://example.com/summer%20sale?q=%C3%BC";
+
+        assert_eq!(encode_location(ENCODED_URL), ENCODED_URL);
+    }
+
+    #[test]
+    fn should_encode_unicode_in_location() {
+        assert_eq!(
+            encode_location("https://münchen.de/straße"),
+            "https://xn--mnchen-3ya.de/stra%C3%9Fe"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! The views of the URLs waiting to be flushed to the DB, accumulated by
+//! [`get_url`](crate::get_url) when the view counts are batched with
+//! [`Config::batch_view_counts`](crate::Config::batch_view_counts).
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::config::ViewCountBatching;
+
+/// The views waiting to be flushed.
+#[derive(Debug, Default)]
+struct Pending {
+    /// Number of pending views of each short ID.
+    counts: BTreeMap<String, u32>,
+    /// Total number of pending views.
+    total: usize,
+    /// When the oldest pending view happened. Unset if no view is pending.
+    since: Option<Instant>,
+    /// Set while a task flushing the views once they are due is running.
+    has_flush_task: bool,
+}
+
+/// The pending views of the URLs of a configuration. Every clone of a
+/// `PendingViews` shares the same views.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PendingViews {
+    /// The pending views.
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl PendingViews {
+    /// Locks the pending views.
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().expect("view counts lock was poisoned")
+    }
+
+    /// Adds a pending view of the provided short ID.
+    ///
+    /// # Returns
+    ///
+    /// Whether the pending views must be flushed according to `batching`.
+    pub(crate) fn record(
+        &self,
+        short_id: &str,
+        batching: ViewCountBatching,
+    ) -> bool {
+        let mut pending = self.lock();
+
+        let count = pending.counts.entry(short_id.to_string()).or_default();
+        *count = count.saturating_add(1);
+        pending.total += 1;
+        let since = *pending.since.get_or_insert_with(Instant::now);
+
+        pending.total >= batching.max_pending
+            || since.elapsed() >= batching.max_delay
+    }
+
+    /// Marks a task flushing the views once they are due as running, if views
+    /// are pending and no such task is running yet.
+    ///
+    /// # Returns
+    ///
+    /// Whether the caller must start the task.
+    pub(crate) fn start_flush_task(&self) -> bool {
+        let mut pending = self.lock();
+
+        let must_start = pending.since.is_some() && !pending.has_flush_task;
+        pending.has_flush_task |= must_start;
+        must_start
+    }
+
+    /// Gets the time left until the pending views must be flushed, which is
+    /// zero once `max_delay` elapsed since the oldest pending view.
+    ///
+    /// # Returns
+    ///
+    /// The time left, or `None` if no view is pending. The flush task must
+    /// stop once `None` is returned, and is then marked as stopped.
+    pub(crate) fn time_until_due(
+        &self,
+        max_delay: Duration,
+    ) -> Option<Duration> {
+        let mut pending = self.lock();
+
+        let time_left = pending
+            .since
+            .map(|since| max_delay.saturating_sub(since.elapsed()));
+        pending.has_flush_task &= time_left.is_some();
+        time_left
+    }
+
+    /// Takes all the pending views, leaving none pending.
+    pub(crate) fn take(&self) -> BTreeMap<String, u32> {
+        let mut pending = self.lock();
+
+        pending.total = 0;
+        pending.since = None;
+        std::mem::take(&mut pending.counts)
+    }
+
+    /// Adds back views that were taken but could not be flushed, so that they
+    /// are flushed with the next batch.
+    pub(crate) fn restore(&self, counts: BTreeMap<String, u32>) {
+        let mut pending = self.lock();
+
+        for (short_id, views) in counts {
+            let count = pending.counts.entry(short_id).or_default();
+            *count = count.saturating_add(views);
+            pending.total += views as usize;
+        }
+        if !pending.counts.is_empty() {
+            pending.since.get_or_insert_with(Instant::now);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#![cfg(all(feature = "metrics", feature = "cache"))]
+
+mod common;
+
+use metrics_util::{
+    debugging::{DebugValue, DebuggingRecorder},
+    CompositeKey, MetricKind,
+};
+use shorty::{
+    create_url, get_url, init,
+    metrics::{URL_CACHE_HITS, URL_HITS},
+};
+
+#[tokio::test]
+async fn test_cached_get_url_increments_cache_hits() {
+    common::setup().await;
+    init(common::config().cache_capacity(16));
+
+    let short_id = create_url("https://example.com/cache-metrics")
+        .await
+        .expect("could not shorten URL");
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().expect("could not install the recorder");
+
+    for _ in 0..3 {
+        get_url(&short_id).await.expect("could not get URL");
+    }
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let counter = |name: &'static str| {
+        snapshot.iter().find_map(|(key, _, _, value)| {
+            let expected_key =
+                CompositeKey::new(MetricKind::Counter, name.into());
+            (*key == expected_key).then_some(value)
+        })
+    };
+    assert_eq!(counter(URL_HITS), Some(&DebugValue::Counter(3)));
+    assert_eq!(counter(URL_CACHE_HITS), Some(&DebugValue::Counter(2)));
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::process::{Command, Output};
+
+/// Runs the CLI with the provided arguments.
+fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_shorty"))
+        .args(args)
+        .output()
+        .expect("could not run the CLI")
+}
+
+#[test]
+fn test_invalid_arguments_exit_with_usage_code() {
+    let output = run_cli(&["lengthen", "not/an/id"]);
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_unconfigured_db_exits_with_backend_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_shorty"))
+        .args(["lengthen", "abcdAbc123"])
+        .env_remove("SHORTY_MONGODB_URI")
+        .env_remove("SHORTY_MONGODB_DATABASE")
+        .output()
+        .expect("could not run the CLI");
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+#[ignore = "requires a live MongoDB instance configured in test.env"]
+fn test_lengthen_missing_id_exits_with_not_found_code() {
+    common::load_env();
+
+    let output = run_cli(&["lengthen", "missingId123"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stderr).trim(), "not found");
+}
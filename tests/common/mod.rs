@@ -1,28 +1,60 @@
-use std::env;
+// Not every test binary uses every helper in this module.
+#![allow(dead_code)]
 
-use dotenv;
-use mongodb::{
-    bson::{doc, Document},
-    options::ClientOptions,
-    Client,
-};
+use std::{env, sync::OnceLock};
 
-use shorty::setup_db;
+use futures::TryStreamExt;
+use mongodb::{bson::Document, options::ClientOptions, Client, Collection};
 
-/// Sets up the environment variables and the DB for the test.
+use shorty::{delete_url, init, setup_db, stream_urls, Config};
+
+/// Sets up the environment variables required for the test.
+pub fn load_env() {
+    dotenv::from_filename("test.env").ok();
+}
+
+/// Checks if the tests should run against a live MongoDB instance. The tests
+/// run against the MongoDB instance configured in `test.env` if one is
+/// configured and against the in-memory backend otherwise.
+pub fn is_live_db() -> bool {
+    load_env();
+    env::var("SHORTY_MONGODB_URI").is_ok()
+}
+
+/// Gets the configuration the tests run against. Every call returns a clone
+/// of the same configuration, so the in-memory backend is shared by all the
+/// tests of a test binary.
+pub fn config() -> Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+
+    CONFIG
+        .get_or_init(|| {
+            if is_live_db() {
+                Config::from_env().expect("could not configure from test.env")
+            } else {
+                Config::in_memory()
+            }
+        })
+        .clone()
+}
+
+/// Sets up the library and the DB for the test.
 ///
 /// # Panics
 ///
 /// The database could not be setup.
 pub async fn setup() {
-    dotenv::from_filename("test.env").ok();
-    if let Err(_) = setup_db().await {
+    static INIT: OnceLock<()> = OnceLock::new();
+
+    INIT.get_or_init(|| init(config()));
+    if setup_db().await.is_err() {
         panic!("could not setup DB");
     };
 }
 
-/// Deletes the provided short ID from the DB.
-pub async fn delete_by_short_id(short_id: String) {
+/// Gets the collection where the URLs are stored bypassing the library. Only
+/// usable when running against a live MongoDB instance.
+pub async fn get_urls_collection() -> Collection<Document> {
     let options = ClientOptions::parse(env::var("SHORTY_MONGODB_URI").unwrap())
         .await
         .expect("could not create a client options");
@@ -31,9 +63,25 @@ pub async fn delete_by_short_id(short_id: String) {
     let database =
         client.database(&env::var("SHORTY_MONGODB_DATABASE").unwrap());
 
-    let urls_collection = database.collection::<Document>("urls");
-    urls_collection
-        .delete_many(doc! { "short_id": short_id }, None)
+    database.collection::<Document>("urls")
+}
+
+/// Gets the view count stored for the provided short ID.
+pub async fn get_view_count(short_id: &str) -> u32 {
+    let urls: Vec<_> = stream_urls()
+        .try_collect()
+        .await
+        .expect("could not stream URLs");
+
+    urls.into_iter()
+        .find(|url| url.short_id == short_id)
+        .expect("URL was not found")
+        .view_count
+}
+
+/// Deletes the provided short ID from the DB.
+pub async fn delete_by_short_id(short_id: String) {
+    delete_url(&short_id)
         .await
-        .expect("could not empty urls collection");
+        .expect("could not delete short ID");
 }
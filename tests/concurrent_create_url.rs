@@ -0,0 +1,57 @@
+mod common;
+
+use std::collections::HashSet;
+
+use shorty::{create_url, get_url};
+
+/// Number of concurrent `create_url` calls made for each kind of URL.
+const NUM_CONCURRENT_CALLS: usize = 25;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_create_url() {
+    common::setup().await;
+
+    const SAME_URL: &str = "https://example.com/concurrent";
+
+    let mut handles = Vec::new();
+    for i in 0..NUM_CONCURRENT_CALLS {
+        let different_url = format!("https://example.com/concurrent/{i}");
+
+        handles.push(tokio::spawn(async move {
+            let short_id = create_url(SAME_URL).await;
+            (SAME_URL.to_string(), short_id)
+        }));
+        handles.push(tokio::spawn(async move {
+            let short_id = create_url(&different_url).await;
+            (different_url, short_id)
+        }));
+    }
+
+    let mut created = Vec::new();
+    for handle in handles {
+        let (full_url, short_id) = handle.await.expect("task panicked");
+        let short_id = short_id.expect("could not shorten URL");
+        created.push((full_url, short_id));
+    }
+
+    let unique_ids: HashSet<&str> =
+        created.iter().map(|(_, id)| id.as_str()).collect();
+    assert_eq!(
+        unique_ids.len(),
+        created.len(),
+        "generated IDs are not unique"
+    );
+
+    for (full_url, short_id) in &created {
+        let fetched_url = get_url(short_id)
+            .await
+            .expect("could not get URL")
+            .expect("created short ID was not found");
+        assert_eq!(&fetched_url, full_url);
+    }
+
+    // Cleanup
+    for (_, short_id) in created {
+        common::delete_by_short_id(short_id).await;
+    }
+}
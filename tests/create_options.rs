@@ -0,0 +1,152 @@
+mod common;
+
+use std::time::Duration;
+
+use shorty::{
+    create_url, create_url_with_options, get_url, CreateOptions, IdError,
+    ShortyError,
+};
+
+#[tokio::test]
+async fn test_create_url_with_length_prefix_and_max_views() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/options/generated";
+    let options = CreateOptions::new()
+        .length(8)
+        .prefix("u")
+        .max_views(1)
+        .build();
+    let outcome = create_url_with_options(URL, options)
+        .await
+        .expect("could not shorten URL");
+    assert!(outcome.created);
+    assert!(outcome.short_id.starts_with('u'));
+    assert_eq!(outcome.short_id.len(), 9);
+
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url, None);
+
+    common::delete_by_short_id(outcome.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_vanity_and_expiry() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/options/vanity";
+    let options = CreateOptions::builder()
+        .vanity("optionsSale")
+        .expiry(Duration::from_secs(60 * 60))
+        .build();
+    let outcome = create_url_with_options(URL, options)
+        .await
+        .expect("could not shorten URL");
+    assert_eq!(outcome.short_id, "optionsSale");
+
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    common::delete_by_short_id(outcome.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_elapsed_expiry() {
+    common::setup().await;
+
+    let options = CreateOptions::builder().expiry(Duration::ZERO).build();
+    let outcome =
+        create_url_with_options("https://example.com/options/expired", options)
+            .await
+            .expect("could not shorten URL");
+
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url, None);
+
+    common::delete_by_short_id(outcome.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_normalization_and_deduplication() {
+    common::setup().await;
+
+    let options = || {
+        CreateOptions::builder()
+            .normalize(true)
+            .deduplicate(true)
+            .build()
+    };
+
+    let first =
+        create_url_with_options("https://münchen.de/options", options())
+            .await
+            .expect("could not shorten URL");
+    assert!(first.created);
+
+    let second =
+        create_url_with_options("https://xn--mnchen-3ya.de/options", options())
+            .await
+            .expect("could not shorten URL");
+    assert!(!second.created);
+    assert_eq!(second.short_id, first.short_id);
+
+    let full_url = get_url(&first.short_id).await.expect("could not get URL");
+    assert_eq!(
+        full_url.as_deref(),
+        Some("https://xn--mnchen-3ya.de/options")
+    );
+
+    common::delete_by_short_id(first.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_too_long_id() {
+    common::setup().await;
+
+    let options = CreateOptions::builder().length(64).build();
+    let create_result =
+        create_url_with_options("https://example.com/options", options).await;
+    assert!(matches!(
+        create_result,
+        Err(ShortyError::InvalidId(IdError::TooLong))
+    ));
+}
+
+#[tokio::test]
+async fn test_deduplication_with_vanity_rejected() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/options/deduplicated-vanity";
+    let existing_id = create_url(URL).await.expect("could not shorten URL");
+
+    let options = CreateOptions::builder()
+        .vanity("optionsDedup")
+        .deduplicate(true)
+        .build();
+    let create_result = create_url_with_options(URL, options).await;
+    assert!(
+        matches!(
+            create_result,
+            Err(ShortyError::ConflictingOptions("vanity"))
+        ),
+        "received: {create_result:?}"
+    );
+
+    let options = CreateOptions::builder()
+        .max_views(1)
+        .expiry(Duration::from_secs(60))
+        .deduplicate(true)
+        .build();
+    let create_result = create_url_with_options(URL, options).await;
+    assert!(
+        matches!(
+            create_result,
+            Err(ShortyError::ConflictingOptions("expiry"))
+        ),
+        "received: {create_result:?}"
+    );
+
+    common::delete_by_short_id(existing_id).await;
+}
@@ -1,6 +1,10 @@
 mod common;
 
-use shorty::create_url;
+use shorty::{
+    create_url, create_url_deduplicated, create_url_dry_run,
+    create_url_with_id, create_url_with_max_views, create_url_with_prefix,
+    exists, get_url, IdError, ShortyError,
+};
 
 #[tokio::test]
 async fn test_create_new_url() {
@@ -16,3 +20,140 @@ async fn test_create_new_url() {
 
     common::delete_by_short_id(create_url_result.unwrap()).await;
 }
+
+#[tokio::test]
+async fn test_create_url_idn() {
+    common::setup().await;
+
+    for url in ["https://münchen.de", "https://xn--mnchen-3ya.de"] {
+        let short_id = create_url(url).await.expect("could not shorten IDN");
+        common::delete_by_short_id(short_id).await;
+    }
+}
+
+#[tokio::test]
+async fn test_create_url_invalid_url() {
+    common::setup().await;
+
+    let create_url_result = create_url("ftp://example.com").await;
+    assert!(matches!(create_url_result, Err(ShortyError::InvalidUrl)));
+}
+
+#[tokio::test]
+async fn test_create_url_deduplicated() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/deduplicated";
+    let first = create_url_deduplicated(URL)
+        .await
+        .expect("could not shorten URL");
+    assert!(first.created);
+
+    let second = create_url_deduplicated(URL)
+        .await
+        .expect("could not shorten URL");
+    assert!(!second.created);
+    assert_eq!(first.short_id, second.short_id);
+
+    common::delete_by_short_id(first.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_deduplicated_ignores_capped_url() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/deduplicated/capped";
+    let capped_id = create_url_with_max_views(URL, 1)
+        .await
+        .expect("could not shorten URL");
+    get_url(&capped_id).await.expect("could not get URL");
+
+    let outcome = create_url_deduplicated(URL)
+        .await
+        .expect("could not shorten URL");
+    assert!(outcome.created);
+    assert_ne!(outcome.short_id, capped_id);
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    common::delete_by_short_id(capped_id).await;
+    common::delete_by_short_id(outcome.short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_id() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/vanity";
+    const SHORT_ID: &str = "vanityTest1";
+    let short_id = create_url_with_id(URL, SHORT_ID)
+        .await
+        .expect("could not shorten URL with vanity ID");
+    assert_eq!(short_id, SHORT_ID);
+
+    let taken_result = create_url_with_id(URL, SHORT_ID).await;
+    assert!(matches!(taken_result, Err(ShortyError::IdTaken)));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_invalid_id() {
+    common::setup().await;
+
+    let create_result =
+        create_url_with_id("https://example.com/vanity", "my/id").await;
+    assert!(matches!(
+        create_result,
+        Err(ShortyError::InvalidId(IdError::InvalidCharacter('/')))
+    ));
+}
+
+#[tokio::test]
+async fn test_create_url_with_prefix() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/prefixed";
+    let short_id = create_url_with_prefix(URL, "u")
+        .await
+        .expect("could not shorten URL with prefix");
+    assert!(short_id.starts_with('u'));
+
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_invalid_prefix() {
+    common::setup().await;
+
+    let create_result =
+        create_url_with_prefix("https://example.com/prefixed", "f/").await;
+    assert!(matches!(
+        create_result,
+        Err(ShortyError::InvalidId(IdError::InvalidCharacter('/')))
+    ));
+}
+
+#[tokio::test]
+async fn test_create_url_dry_run() {
+    common::setup().await;
+
+    let candidate_id = create_url_dry_run("https://example.com/dry-run")
+        .await
+        .expect("could not perform a dry run");
+
+    let candidate_exists =
+        exists(&candidate_id).await.expect("could not check ID");
+    assert!(!candidate_exists, "dry run saved the URL");
+}
+
+#[tokio::test]
+async fn test_create_url_dry_run_invalid_url() {
+    common::setup().await;
+
+    let dry_run_result = create_url_dry_run("ftp://example.com").await;
+    assert!(matches!(dry_run_result, Err(ShortyError::InvalidUrl)));
+}
@@ -0,0 +1,21 @@
+use shorty::{get_url, init, Config, ShortyError};
+
+/// Points the library at a MongoDB instance that cannot be reached so that
+/// every DB operation fails quickly.
+fn setup_unreachable_db() {
+    init(Config::new(
+        "mongodb://127.0.0.1:1/?serverSelectionTimeoutMS=100",
+        "shorty_unreachable",
+    ));
+}
+
+#[tokio::test]
+async fn test_get_url_db_error() {
+    setup_unreachable_db();
+
+    let full_url = get_url("abcd1234").await;
+    assert!(
+        matches!(full_url, Err(ShortyError::Database(_))),
+        "expected a database error, received: {full_url:?}"
+    );
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use shorty::{create_url, get_url, init, ShortyError};
+
+#[tokio::test]
+async fn test_default_scheme() {
+    common::setup().await;
+    init(common::config().default_scheme("https"));
+
+    // A bare host is coerced.
+    let short_id = create_url("example.com")
+        .await
+        .expect("could not shorten URL");
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some("https://example.com"));
+    common::delete_by_short_id(short_id).await;
+
+    // A URL with a scheme is left as is.
+    const URL: &str = "http://example.com/default-scheme";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+    common::delete_by_short_id(short_id).await;
+
+    // Anything else is still rejected.
+    let result = create_url("not a host").await;
+    assert!(matches!(result, Err(ShortyError::InvalidUrl)));
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use shorty::{create_url, find_by_full_url};
+
+#[tokio::test]
+async fn test_find_by_full_url() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/reverse-lookup";
+    let first_id = create_url(URL).await.expect("could not shorten URL");
+    let second_id = create_url(URL).await.expect("could not shorten URL");
+
+    let short_ids = find_by_full_url(URL).await.expect("could not find URL");
+    assert_eq!(short_ids.len(), 2);
+    assert!(short_ids.contains(&first_id));
+    assert!(short_ids.contains(&second_id));
+
+    // Cleanup
+    common::delete_by_short_id(first_id).await;
+    common::delete_by_short_id(second_id).await;
+}
+
+#[tokio::test]
+async fn test_find_by_full_url_not_shortened() {
+    common::setup().await;
+
+    let short_ids = find_by_full_url("https://example.com/never-shortened")
+        .await
+        .expect("could not find URL");
+    assert!(short_ids.is_empty());
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use shorty::{
+    create_url, create_url_with_max_views, find_by_full_url, get_or_create,
+    get_url,
+};
+
+#[tokio::test]
+async fn test_get_or_create_new_url() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/get-or-create/new";
+    let short_id = get_or_create(URL).await.expect("could not shorten URL");
+
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_get_or_create_existing_url() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/get-or-create/existing";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    let existing_id = get_or_create(URL).await.expect("could not shorten URL");
+    assert_eq!(existing_id, short_id);
+    let existing_id = get_or_create(URL).await.expect("could not shorten URL");
+    assert_eq!(existing_id, short_id);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_get_or_create_ignores_capped_url() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/get-or-create/capped";
+    let capped_id = create_url_with_max_views(URL, 1)
+        .await
+        .expect("could not shorten URL");
+    get_url(&capped_id).await.expect("could not get URL");
+
+    let short_id = get_or_create(URL).await.expect("could not shorten URL");
+    assert_ne!(short_id, capped_id);
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    common::delete_by_short_id(capped_id).await;
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_get_or_create() {
+    common::setup().await;
+
+    const NUM_CONCURRENT_CALLS: usize = 20;
+    const URL: &str = "https://example.com/get-or-create/concurrent";
+
+    let mut handles = Vec::new();
+    for _ in 0..NUM_CONCURRENT_CALLS {
+        handles.push(tokio::spawn(get_or_create(URL)));
+    }
+
+    let mut short_ids = Vec::new();
+    for handle in handles {
+        let short_id = handle
+            .await
+            .expect("get_or_create task panicked")
+            .expect("could not shorten URL");
+        short_ids.push(short_id);
+    }
+
+    short_ids.dedup();
+    assert_eq!(short_ids.len(), 1, "more than one short ID was created");
+
+    let stored_ids = find_by_full_url(URL)
+        .await
+        .expect("could not find the URLs");
+    assert_eq!(stored_ids, short_ids);
+
+    common::delete_by_short_id(short_ids.remove(0)).await;
+}
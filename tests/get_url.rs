@@ -32,3 +32,32 @@ async fn test_get_url_invalid_id() {
     let full_url = full_url.unwrap();
     assert!(full_url.is_none());
 }
+
+/// Concurrent views of the same URL must all be counted.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_get_url_counted() {
+    const NUM_CONCURRENT_CALLS: u32 = 50;
+
+    common::setup().await;
+
+    let short_id = create_url("https://example.com/concurrent-views")
+        .await
+        .expect("could not shorten URL");
+
+    let mut handles = Vec::new();
+    for _ in 0..NUM_CONCURRENT_CALLS {
+        let short_id = short_id.clone();
+        handles.push(tokio::spawn(async move { get_url(&short_id).await }));
+    }
+    for handle in handles {
+        let full_url = handle.await.expect("task panicked");
+        assert!(full_url.expect("could not get URL").is_some());
+    }
+
+    assert_eq!(
+        common::get_view_count(&short_id).await,
+        NUM_CONCURRENT_CALLS
+    );
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use shorty::{create_url, create_url_with_max_views, get_url_and_count};
+
+#[tokio::test]
+async fn test_get_url_and_count() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/counted";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    for expected_count in 1..=3 {
+        let (full_url, view_count) = get_url_and_count(&short_id)
+            .await
+            .expect("could not get URL")
+            .expect("URL was not found");
+        assert_eq!(full_url, URL);
+        assert_eq!(view_count, expected_count);
+        assert_eq!(common::get_view_count(&short_id).await, view_count);
+    }
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_get_url_and_count_not_found() {
+    common::setup().await;
+
+    let outcome = get_url_and_count("aaaaaaaaaa").await;
+    assert!(matches!(outcome, Ok(None)), "received: {outcome:?}");
+}
+
+#[tokio::test]
+async fn test_get_url_and_count_past_max_views() {
+    common::setup().await;
+
+    let short_id =
+        create_url_with_max_views("https://example.com/counted-once", 1)
+            .await
+            .expect("could not shorten URL");
+
+    let outcome = get_url_and_count(&short_id)
+        .await
+        .expect("could not get URL");
+    assert_eq!(outcome.map(|(_, view_count)| view_count), Some(1));
+
+    let outcome = get_url_and_count(&short_id)
+        .await
+        .expect("could not get URL");
+    assert_eq!(outcome, None);
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use shorty::{
+    create_url_with_id, create_url_with_options, get_url, init, CreateOptions,
+    ShortyError,
+};
+
+/// Every character a generated short ID can contain.
+const ID_CHARACTERS: &str =
+    "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+#[tokio::test]
+async fn test_id_length_grows_on_collisions() {
+    common::setup().await;
+    init(common::config().grow_id_length(3, 2));
+
+    // Take every short ID of a single character.
+    for short_id in ID_CHARACTERS.chars().map(String::from) {
+        match create_url_with_id("https://example.com/taken", &short_id).await {
+            Ok(_) | Err(ShortyError::IdTaken) => {}
+            Err(err) => panic!("could not take short ID {short_id}: {err}"),
+        }
+    }
+
+    const URL: &str = "https://example.com/grown";
+    let options = || CreateOptions::builder().length(1).build();
+    let outcome = create_url_with_options(URL, options())
+        .await
+        .expect("could not shorten URL");
+    assert_eq!(outcome.short_id.len(), 2);
+
+    let full_url = get_url(&outcome.short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    // The short IDs must not grow past the maximum length.
+    init(common::config().grow_id_length(3, 1));
+    let create_result = create_url_with_options(URL, options()).await;
+    assert!(matches!(create_result, Err(ShortyError::IdGeneration)));
+
+    common::delete_by_short_id(outcome.short_id).await;
+    for short_id in ID_CHARACTERS.chars().map(String::from) {
+        common::delete_by_short_id(short_id).await;
+    }
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use shorty::{create_url, init};
+
+/// The seed of the generated short IDs.
+const SEED: u64 = 42;
+
+#[tokio::test]
+async fn test_seeded_id_collision_retried() {
+    common::setup().await;
+
+    init(common::config().id_seed(SEED));
+    let first_id = create_url("https://example.com/seeded")
+        .await
+        .expect("could not shorten URL");
+    assert_eq!(first_id, "WXdP2Pjq80");
+
+    // The same seed generates the taken short ID first, so the next short ID
+    // of the sequence is used instead.
+    init(common::config().id_seed(SEED));
+    let second_id = create_url("https://example.com/seeded")
+        .await
+        .expect("could not shorten URL");
+    assert_eq!(second_id, "vVO8WD0W3e");
+
+    common::delete_by_short_id(first_id).await;
+    common::delete_by_short_id(second_id).await;
+}
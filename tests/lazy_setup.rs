@@ -0,0 +1,63 @@
+mod common;
+
+use mongodb::bson::doc;
+use shorty::{create_url, init, Config, MongoShortener, Shortener};
+
+/// `create_url` must set up the unique index on the short IDs itself when
+/// `setup_db` was never called.
+#[tokio::test]
+#[ignore = "requires a live MongoDB instance configured in test.env"]
+async fn test_create_url_without_setup_db() {
+    init(common::config());
+
+    let urls_collection = common::get_urls_collection().await;
+    urls_collection
+        .drop(None)
+        .await
+        .expect("could not drop urls collection");
+
+    let short_id = create_url("https://example.com/lazy-setup")
+        .await
+        .expect("could not shorten URL");
+
+    let duplicate_insert = urls_collection
+        .insert_one(
+            doc! {
+                "short_id": &short_id,
+                "full_url": "https://example.com/duplicate",
+                "view_count": 0,
+            },
+            None,
+        )
+        .await;
+    assert!(
+        duplicate_insert.is_err(),
+        "inserted a duplicate short ID without a unique index"
+    );
+
+    common::delete_by_short_id(short_id).await;
+}
+
+/// Creating a URL must set up every index when the DB was never set up, so
+/// that setting up the DB afterwards finds them all present.
+#[tokio::test]
+async fn test_create_url_sets_up_indexes() {
+    let shortener = MongoShortener::new(Config::in_memory());
+
+    shortener
+        .create("https://example.com/lazy-setup")
+        .await
+        .expect("could not shorten URL");
+
+    let report = shortener.setup().await.expect("could not setup the DB");
+    assert!(report.created.is_empty(), "indexes were not set up lazily");
+    assert_eq!(
+        report.already_present,
+        [
+            "short_id_1",
+            "full_url_1",
+            "full_url_1_canonical_1",
+            "metadata.$**_1"
+        ]
+    );
+}
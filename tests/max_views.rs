@@ -0,0 +1,63 @@
+mod common;
+
+use shorty::{create_url_with_max_views, get_url};
+
+/// Number of times the URLs of the tests can be fetched.
+const MAX_VIEWS: u32 = 3;
+
+#[tokio::test]
+async fn test_get_url_up_to_max_views() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/max-views";
+    let short_id = create_url_with_max_views(URL, MAX_VIEWS)
+        .await
+        .expect("could not shorten URL");
+
+    for _ in 0..MAX_VIEWS {
+        let full_url = get_url(&short_id).await.expect("could not get URL");
+        assert_eq!(full_url.as_deref(), Some(URL));
+    }
+
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url, None);
+    assert_eq!(common::get_view_count(&short_id).await, MAX_VIEWS);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_get_url_up_to_max_views() {
+    common::setup().await;
+
+    const NUM_CONCURRENT_CALLS: usize = 20;
+
+    let short_id = create_url_with_max_views(
+        "https://example.com/max-views/concurrent",
+        MAX_VIEWS,
+    )
+    .await
+    .expect("could not shorten URL");
+
+    let mut handles = Vec::new();
+    for _ in 0..NUM_CONCURRENT_CALLS {
+        let short_id = short_id.clone();
+        handles.push(tokio::spawn(async move { get_url(&short_id).await }));
+    }
+
+    let mut num_resolved = 0;
+    for handle in handles {
+        let full_url = handle
+            .await
+            .expect("get_url task panicked")
+            .expect("could not get URL");
+        if full_url.is_some() {
+            num_resolved += 1;
+        }
+    }
+
+    assert_eq!(num_resolved, MAX_VIEWS);
+    assert_eq!(common::get_view_count(&short_id).await, MAX_VIEWS);
+
+    common::delete_by_short_id(short_id).await;
+}
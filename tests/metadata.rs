@@ -0,0 +1,87 @@
+mod common;
+
+use futures::TryStreamExt;
+use shorty::{
+    create_url_with_options, list_urls_by_tag, stream_urls, CreateOptions,
+    ShortyError,
+};
+
+/// Shortens the provided URL labelled with the provided team.
+async fn create_url_for_team(full_url: &str, team: &str) -> String {
+    let options = CreateOptions::builder()
+        .metadata("owner", "alice")
+        .metadata("team", team)
+        .build();
+
+    create_url_with_options(full_url, options)
+        .await
+        .expect("could not shorten URL")
+        .short_id
+}
+
+#[tokio::test]
+async fn test_metadata_read_back() {
+    common::setup().await;
+
+    let short_id =
+        create_url_for_team("https://example.com/metadata", "metadata").await;
+
+    let urls: Vec<_> = stream_urls()
+        .try_collect()
+        .await
+        .expect("could not stream URLs");
+    let url = urls
+        .into_iter()
+        .find(|url| url.short_id == short_id)
+        .expect("URL was not found");
+    assert_eq!(url.metadata.get("owner").map(String::as_str), Some("alice"));
+    assert_eq!(
+        url.metadata.get("team").map(String::as_str),
+        Some("metadata")
+    );
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_list_urls_by_tag() {
+    common::setup().await;
+
+    let growth_id =
+        create_url_for_team("https://example.com/metadata/growth", "growth")
+            .await;
+    let search_id =
+        create_url_for_team("https://example.com/metadata/search", "search")
+            .await;
+
+    let urls = list_urls_by_tag("team", "growth")
+        .await
+        .expect("could not list URLs");
+    assert_eq!(urls.len(), 1);
+    assert_eq!(urls[0].short_id, growth_id);
+    assert_eq!(urls[0].full_url, "https://example.com/metadata/growth");
+
+    let urls = list_urls_by_tag("team", "unknown")
+        .await
+        .expect("could not list URLs");
+    assert!(urls.is_empty());
+
+    common::delete_by_short_id(growth_id).await;
+    common::delete_by_short_id(search_id).await;
+}
+
+#[tokio::test]
+async fn test_invalid_metadata_key_rejected() {
+    common::setup().await;
+
+    let options = CreateOptions::builder().metadata("team.name", "x").build();
+    let result = create_url_with_options(
+        "https://example.com/metadata/invalid",
+        options,
+    )
+    .await;
+    assert!(matches!(result, Err(ShortyError::InvalidMetadataKey(_))));
+
+    let result = list_urls_by_tag("$where", "x").await;
+    assert!(matches!(result, Err(ShortyError::InvalidMetadataKey(_))));
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use metrics_util::{
+    debugging::{DebugValue, DebuggingRecorder},
+    CompositeKey, MetricKind,
+};
+use shorty::{get_url, metrics::URL_NOT_FOUND};
+
+#[tokio::test]
+async fn test_get_url_miss_increments_not_found() {
+    common::setup().await;
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().expect("could not install the recorder");
+
+    let full_url = get_url("this_id_does_not_exist").await;
+    assert!(full_url.is_ok());
+    assert!(full_url.unwrap().is_none());
+
+    let not_found_count = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find_map(|(key, _, _, value)| {
+            let expected_key =
+                CompositeKey::new(MetricKind::Counter, URL_NOT_FOUND.into());
+            (key == expected_key).then_some(value)
+        });
+    assert_eq!(not_found_count, Some(DebugValue::Counter(1)));
+}
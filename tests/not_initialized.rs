@@ -0,0 +1,32 @@
+use std::env;
+
+use shorty::{create_url, get_url, ShortyError};
+
+/// Removes the environment variables that could be used to configure the
+/// library.
+fn clear_env() {
+    env::remove_var("SHORTY_MONGODB_URI");
+    env::remove_var("SHORTY_MONGODB_DATABASE");
+}
+
+#[tokio::test]
+async fn test_get_url_not_initialized() {
+    clear_env();
+
+    let full_url = get_url("abcd1234").await;
+    assert!(
+        matches!(full_url, Err(ShortyError::NotInitialized)),
+        "expected a not initialised error, received: {full_url:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_create_url_not_initialized() {
+    clear_env();
+
+    let short_id = create_url("https://example.com").await;
+    assert!(
+        matches!(short_id, Err(ShortyError::NotInitialized)),
+        "expected a not initialised error, received: {short_id:?}"
+    );
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "danger-purge")]
+
+use shorty::{count_urls, create_url, init, purge_all, Config};
+
+/// Always runs against its own in-memory backend, since purging a live DB
+/// would delete the URLs of the tests running concurrently.
+#[tokio::test]
+async fn test_purge_all() {
+    init(Config::in_memory());
+
+    for path in ["a", "b", "c"] {
+        create_url(&format!("https://example.com/purge/{path}"))
+            .await
+            .expect("could not shorten URL");
+    }
+    assert_eq!(count_urls().await.expect("could not count URLs"), 3);
+
+    let num_deleted = purge_all().await.expect("could not purge URLs");
+    assert_eq!(num_deleted, 3);
+    assert_eq!(count_urls().await.expect("could not count URLs"), 0);
+}
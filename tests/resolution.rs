@@ -0,0 +1,65 @@
+mod common;
+
+use std::time::Duration;
+
+use shorty::{
+    create_url, create_url_with_max_views, create_url_with_options, resolve,
+    CreateOptions, Resolution,
+};
+
+#[tokio::test]
+async fn test_resolve_found() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/resolution/found";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    let resolution = resolve(&short_id).await.expect("could not resolve");
+    assert_eq!(resolution, Resolution::Found(URL.to_string()));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_resolve_not_found() {
+    common::setup().await;
+
+    let resolution = resolve("doesNotExist").await.expect("could not resolve");
+    assert_eq!(resolution, Resolution::NotFound);
+}
+
+#[tokio::test]
+async fn test_resolve_gone_after_max_views() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/resolution/max-views";
+    let short_id = create_url_with_max_views(URL, 1)
+        .await
+        .expect("could not shorten URL");
+
+    let resolution = resolve(&short_id).await.expect("could not resolve");
+    assert_eq!(resolution, Resolution::Found(URL.to_string()));
+    let resolution = resolve(&short_id).await.expect("could not resolve");
+    assert_eq!(resolution, Resolution::Gone);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_resolve_gone_after_expiry() {
+    common::setup().await;
+
+    let options = CreateOptions::builder().expiry(Duration::ZERO).build();
+    let outcome = create_url_with_options(
+        "https://example.com/resolution/expiry",
+        options,
+    )
+    .await
+    .expect("could not shorten URL");
+
+    let resolution =
+        resolve(&outcome.short_id).await.expect("could not resolve");
+    assert_eq!(resolution, Resolution::Gone);
+
+    common::delete_by_short_id(outcome.short_id).await;
+}
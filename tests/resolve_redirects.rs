@@ -0,0 +1,154 @@
+#![cfg(feature = "resolve")]
+
+mod common;
+
+use std::{net::SocketAddr, time::Duration};
+
+use shorty::{
+    create_url, get_url, init, MongoShortener, ResolveError, Shortener,
+    ShortyError,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+const MAX_REDIRECT_HOPS: usize = 5;
+
+/// Gets the response of the mock server for the provided path.
+fn respond(path: &str) -> String {
+    let location = match path {
+        "/a" => Some("/b"),
+        "/loop-a" => Some("/loop-b"),
+        "/loop-b" => Some("/loop-a"),
+        "/ftp" => Some("ftp://example.com/file"),
+        _ => None,
+    };
+
+    match location {
+        Some(location) => format!(
+            "HTTP/1.1 302 Found\r\nLocation: {location}\r\n\
+            Content-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+        None => "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\
+            Connection: close\r\n\r\n"
+            .to_string(),
+    }
+}
+
+/// Starts a mock HTTP server redirecting `/a` to `/b`.
+async fn start_mock_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("could not bind mock server");
+    let addr = listener.local_addr().expect("could not get mock address");
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let read = stream.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+                let _ = stream.write_all(respond(path).as_bytes()).await;
+            });
+        }
+    });
+
+    addr
+}
+
+/// Starts a mock HTTP server accepting connections without ever responding.
+async fn start_stalled_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("could not bind mock server");
+    let addr = listener.local_addr().expect("could not get mock address");
+
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            // The streams are kept open so that the requests stall.
+            streams.push(stream);
+        }
+    });
+
+    addr
+}
+
+/// Sets up the library to resolve the redirects of the created URLs.
+async fn setup() {
+    common::setup().await;
+    init(common::config().resolve_redirects(MAX_REDIRECT_HOPS));
+}
+
+#[tokio::test]
+async fn test_create_url_stores_redirect_destination() {
+    setup().await;
+    let addr = start_mock_server().await;
+
+    let short_id = create_url(&format!("http://{addr}/a"))
+        .await
+        .expect("could not shorten URL");
+
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url, Some(format!("http://{addr}/b")));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_without_redirect() {
+    setup().await;
+    let addr = start_mock_server().await;
+
+    let url = format!("http://{addr}/b");
+    let short_id = create_url(&url).await.expect("could not shorten URL");
+
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url, Some(url));
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_create_url_with_redirect_loop() {
+    setup().await;
+    let addr = start_mock_server().await;
+
+    let result = create_url(&format!("http://{addr}/loop-a")).await;
+    assert!(matches!(
+        result,
+        Err(ShortyError::Resolve(ResolveError::Loop))
+    ));
+}
+
+#[tokio::test]
+async fn test_create_url_with_redirect_to_non_http_scheme() {
+    setup().await;
+    let addr = start_mock_server().await;
+
+    let result = create_url(&format!("http://{addr}/ftp")).await;
+    assert!(matches!(result, Err(ShortyError::InvalidUrl)));
+}
+
+#[tokio::test]
+async fn test_create_url_with_stalled_target() {
+    common::setup().await;
+    let addr = start_stalled_server().await;
+    let shortener = MongoShortener::new(
+        common::config()
+            .resolve_redirects(MAX_REDIRECT_HOPS)
+            .resolve_timeout(
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+            ),
+    );
+
+    let result = shortener.create(&format!("http://{addr}/a")).await;
+    assert!(matches!(
+        result,
+        Err(ShortyError::Resolve(ResolveError::Request(err))) if err.is_timeout()
+    ));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use shorty::{
+    create_url, create_url_with_id, init, MongoShortener, Shortener,
+    ShortyError,
+};
+
+/// Sets up the library to serve the short links from `sho.rt`.
+async fn setup() {
+    common::setup().await;
+    init(common::config().base_url("https://sho.rt"));
+}
+
+#[tokio::test]
+async fn test_create_url_pointing_at_base_url() {
+    setup().await;
+
+    let create_result = create_url("https://sho.rt/xyz").await;
+    assert!(matches!(create_result, Err(ShortyError::SelfReference)));
+
+    let create_result =
+        create_url_with_id("https://SHO.RT/xyz", "selfRef").await;
+    assert!(matches!(create_result, Err(ShortyError::SelfReference)));
+}
+
+#[tokio::test]
+async fn test_create_url_pointing_at_other_host() {
+    setup().await;
+
+    let short_id = create_url("https://example.com/xyz")
+        .await
+        .expect("could not shorten URL");
+
+    common::delete_by_short_id(short_id).await;
+}
+
+/// The test owns its configuration, so that setting a bare host as the base
+/// URL does not race with the configuration of the other tests.
+#[tokio::test]
+async fn test_create_url_pointing_at_bare_host_base_url() {
+    common::setup().await;
+    let shortener = MongoShortener::new(common::config().base_url("sho.rt"));
+
+    let create_result = shortener.create("https://sho.rt/xyz").await;
+    assert!(matches!(create_result, Err(ShortyError::SelfReference)));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "server")]
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use shorty::{
+    create_url, create_url_with_max_views,
+    server::{router, RedirectStatus},
+};
+use tower::ServiceExt;
+
+/// Requests the short ID from a router redirecting with the provided status.
+async fn request_short_id(
+    redirect_status: RedirectStatus,
+    short_id: &str,
+) -> axum::response::Response {
+    let request = Request::builder()
+        .uri(format!("/{short_id}"))
+        .body(Body::empty())
+        .expect("could not build request");
+
+    router(redirect_status)
+        .oneshot(request)
+        .await
+        .expect("could not send request")
+}
+
+#[tokio::test]
+async fn test_permanent_redirect() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/permanent";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    let response = request_short_id(RedirectStatus::Permanent, &short_id).await;
+    assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.headers()[header::LOCATION], URL);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_temporary_redirect() {
+    common::setup().await;
+
+    const URL: &str = "https://example.com/temporary";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    let response = request_short_id(RedirectStatus::Temporary, &short_id).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.headers()[header::LOCATION], URL);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_redirect_not_found() {
+    common::setup().await;
+
+    let response =
+        request_short_id(RedirectStatus::Temporary, "doesNotExist").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_redirect_gone() {
+    common::setup().await;
+
+    let short_id =
+        create_url_with_max_views("https://example.com/server/gone", 1)
+            .await
+            .expect("could not shorten URL");
+
+    let response = request_short_id(RedirectStatus::Temporary, &short_id).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let response = request_short_id(RedirectStatus::Temporary, &short_id).await;
+    assert_eq!(response.status(), StatusCode::GONE);
+
+    common::delete_by_short_id(short_id).await;
+}
+
+#[tokio::test]
+async fn test_redirect_encodes_location() {
+    common::setup().await;
+
+    let short_id = create_url("https://example.com/summer sale")
+        .await
+        .expect("could not shorten URL");
+
+    let response = request_short_id(RedirectStatus::Temporary, &short_id).await;
+    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        "https://example.com/summer%20sale"
+    );
+
+    common::delete_by_short_id(short_id).await;
+}
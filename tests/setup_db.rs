@@ -0,0 +1,18 @@
+mod common;
+
+use shorty::{init, setup_db};
+
+#[tokio::test]
+async fn test_setup_db_twice() {
+    init(common::config());
+
+    setup_db().await.expect("could not setup the DB");
+    let report = setup_db().await.expect("could not setup the DB again");
+
+    assert!(report.created.is_empty(), "indexes were created again");
+    assert!(report.already_present.contains(&"short_id_1".to_string()));
+    assert!(report.already_present.contains(&"full_url_1".to_string()));
+    assert!(report
+        .already_present
+        .contains(&"metadata.$**_1".to_string()));
+}
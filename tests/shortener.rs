@@ -0,0 +1,98 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use shorty::{get_url, MongoShortener, Shortener, ShortyError};
+
+/// A [`Shortener`] keeping the URLs in a map, standing in for the DB.
+#[derive(Default)]
+struct FakeShortener {
+    urls: Mutex<HashMap<String, String>>,
+}
+
+impl Shortener for FakeShortener {
+    fn create<'a>(
+        &'a self,
+        full_url: &'a str,
+    ) -> BoxFuture<'a, Result<String, ShortyError>> {
+        let mut urls = self.urls.lock().unwrap();
+        let short_id = format!("fake{}", urls.len());
+        urls.insert(short_id.clone(), full_url.to_string());
+
+        async move { Ok(short_id) }.boxed()
+    }
+
+    fn get<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, ShortyError>> {
+        let full_url = self.urls.lock().unwrap().get(short_id).cloned();
+
+        async move { Ok(full_url) }.boxed()
+    }
+
+    fn delete<'a>(
+        &'a self,
+        short_id: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ShortyError>> {
+        let is_deleted = self.urls.lock().unwrap().remove(short_id).is_some();
+
+        async move { Ok(is_deleted) }.boxed()
+    }
+}
+
+/// A handler of an application resolving short IDs through the shortener in
+/// its state.
+async fn lengthen_handler(
+    shortener: Arc<dyn Shortener>,
+    short_id: &str,
+) -> (u16, String) {
+    match shortener.get(short_id).await {
+        Ok(Some(full_url)) => (302, full_url),
+        Ok(None) => (404, "not found".to_string()),
+        Err(err) => (500, err.to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_handler_with_fake_shortener() {
+    let shortener: Arc<dyn Shortener> = Arc::new(FakeShortener::default());
+
+    let short_id = shortener
+        .create("https://example.com/fake")
+        .await
+        .expect("could not shorten URL");
+
+    let response = lengthen_handler(shortener.clone(), &short_id).await;
+    assert_eq!(response, (302, "https://example.com/fake".to_string()));
+
+    assert!(shortener.delete(&short_id).await.unwrap());
+    let response = lengthen_handler(shortener, &short_id).await;
+    assert_eq!(response.0, 404);
+}
+
+#[tokio::test]
+async fn test_mongo_shortener_uses_its_own_config() {
+    let shortener = MongoShortener::new(common::config());
+    shortener.setup().await.expect("could not setup DB");
+    let shortener: Arc<dyn Shortener> = Arc::new(shortener);
+
+    const URL: &str = "https://example.com/shortener";
+    let short_id = shortener.create(URL).await.expect("could not shorten URL");
+
+    let response = lengthen_handler(shortener.clone(), &short_id).await;
+    assert_eq!(response, (302, URL.to_string()));
+
+    // The library itself was never initialised.
+    assert!(matches!(
+        get_url(&short_id).await,
+        Err(ShortyError::NotInitialized)
+    ));
+
+    assert!(shortener.delete(&short_id).await.unwrap());
+    assert_eq!(shortener.get(&short_id).await.unwrap(), None);
+}
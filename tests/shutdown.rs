@@ -0,0 +1,31 @@
+mod common;
+
+use std::time::Duration;
+
+use shorty::{create_url, get_url, init, shutdown, ShortyError};
+
+#[tokio::test]
+async fn test_shutdown() {
+    init(common::config().batch_view_counts(100, Duration::from_secs(60 * 60)));
+
+    const URL: &str = "https://example.com/shutdown";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+    get_url(&short_id).await.expect("could not get URL");
+
+    shutdown().await.expect("could not shut down");
+    let full_url = get_url(&short_id).await;
+    assert!(
+        matches!(full_url, Err(ShortyError::NotInitialized)),
+        "expected a not initialised error, received: {full_url:?}"
+    );
+    assert!(matches!(shutdown().await, Err(ShortyError::NotInitialized)));
+
+    init(common::config().track_views(false));
+    let full_url = get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(full_url.as_deref(), Some(URL));
+
+    // The pending view was flushed by the shutdown.
+    assert_eq!(common::get_view_count(&short_id).await, 1);
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use futures::TryStreamExt;
+use shorty::{create_url, stream_urls, UrlInfo};
+
+#[tokio::test]
+async fn test_stream_urls() {
+    common::setup().await;
+
+    let mut short_ids = Vec::new();
+    for i in 0..5 {
+        let url = format!("https://example.com/stream/{i}");
+        short_ids.push(create_url(&url).await.expect("could not shorten URL"));
+    }
+
+    let streamed: Vec<UrlInfo> = stream_urls()
+        .try_collect()
+        .await
+        .expect("could not stream URLs");
+
+    for short_id in &short_ids {
+        let count = streamed
+            .iter()
+            .filter(|url| &url.short_id == short_id)
+            .count();
+        assert_eq!(count, 1, "{short_id} was not streamed exactly once");
+    }
+
+    // Cleanup
+    for short_id in short_ids {
+        common::delete_by_short_id(short_id).await;
+    }
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use shorty::{create_url, get_url};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+/// A span recorded by the [`SpanRecorder`].
+#[derive(Debug)]
+struct RecordedSpan {
+    name: &'static str,
+    fields: Vec<(&'static str, String)>,
+}
+
+/// Records the name and fields of every span created.
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<Vec<RecordedSpan>>>,
+}
+
+impl Visit for RecordedSpan {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.push((field.name(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.push((field.name(), format!("{value:?}")));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SpanRecorder {
+    fn on_new_span(
+        &self,
+        attrs: &Attributes<'_>,
+        _id: &Id,
+        _ctx: Context<'_, S>,
+    ) {
+        let mut span = RecordedSpan {
+            name: attrs.metadata().name(),
+            fields: Vec::new(),
+        };
+        attrs.record(&mut span);
+
+        self.spans.lock().unwrap().push(span);
+    }
+}
+
+#[tokio::test]
+async fn test_get_url_emits_span() {
+    common::setup().await;
+
+    let short_id = create_url("https://example.com/traced")
+        .await
+        .expect("could not shorten URL");
+
+    let recorder = SpanRecorder::default();
+    let _guard = tracing_subscriber::registry()
+        .with(recorder.clone())
+        .set_default();
+
+    get_url(&short_id).await.expect("could not get URL");
+
+    {
+        let spans = recorder.spans.lock().unwrap();
+        let get_url_span = spans
+            .iter()
+            .find(|span| span.name == "get_url")
+            .expect("no span was emitted for get_url");
+        assert!(get_url_span
+            .fields
+            .contains(&("short_id", short_id.clone())));
+        assert!(
+            spans
+                .iter()
+                .any(|span| span.name == "db.find_and_increment_view_count"),
+            "no span was emitted for the DB operation"
+        );
+    }
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use shorty::{create_url, get_url, init};
+
+#[tokio::test]
+async fn test_get_url_without_tracking_views() {
+    common::setup().await;
+    init(common::config().track_views(false));
+
+    const URL: &str = "https://example.com/untracked";
+    let short_id = create_url(URL).await.expect("could not shorten URL");
+
+    for _ in 0..3 {
+        let full_url = get_url(&short_id).await.expect("could not get URL");
+        assert_eq!(full_url.as_deref(), Some(URL));
+    }
+
+    assert_eq!(common::get_view_count(&short_id).await, 0);
+
+    common::delete_by_short_id(short_id).await;
+}
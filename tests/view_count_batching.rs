@@ -0,0 +1,39 @@
+mod common;
+
+use std::time::Duration;
+
+use shorty::{create_url, flush_view_counts, get_url, init};
+
+/// Number of pending views after which they are flushed.
+const MAX_PENDING: usize = 10;
+
+#[tokio::test]
+async fn test_batched_view_counts_flushed() {
+    common::setup().await;
+    init(
+        common::config()
+            .batch_view_counts(MAX_PENDING, Duration::from_secs(60 * 60)),
+    );
+
+    let short_id = create_url("https://example.com/batched-views")
+        .await
+        .expect("could not shorten URL");
+
+    for _ in 0..4 {
+        get_url(&short_id).await.expect("could not get URL");
+    }
+    assert_eq!(common::get_view_count(&short_id).await, 0);
+
+    let views = flush_view_counts().await.expect("could not flush views");
+    assert_eq!(views, 4);
+    assert_eq!(common::get_view_count(&short_id).await, 4);
+
+    // Reaching the maximum number of pending views flushes them.
+    for _ in 0..MAX_PENDING {
+        get_url(&short_id).await.expect("could not get URL");
+    }
+    assert_eq!(common::get_view_count(&short_id).await, 14);
+    assert_eq!(flush_view_counts().await.expect("could not flush views"), 0);
+
+    common::delete_by_short_id(short_id).await;
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use std::time::Duration;
+
+use shorty::{create_url, get_url, init};
+
+/// Kept apart from the other batching tests, since every clone of the test
+/// configuration shares the same pending views.
+#[tokio::test]
+async fn test_batched_view_counts_flushed_after_delay() {
+    common::setup().await;
+    init(common::config().batch_view_counts(10, Duration::from_millis(50)));
+
+    let short_id = create_url("https://example.com/delayed-views")
+        .await
+        .expect("could not shorten URL");
+
+    get_url(&short_id).await.expect("could not get URL");
+    assert_eq!(common::get_view_count(&short_id).await, 0);
+
+    // No other view happens, so the pending view is flushed in the background.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(common::get_view_count(&short_id).await, 1);
+
+    common::delete_by_short_id(short_id).await;
+}